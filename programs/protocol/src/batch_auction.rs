@@ -0,0 +1,443 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+use crate::constants::*;
+use crate::errors::*;
+use crate::presale::validate_listing_terms;
+use crate::state::*;
+use crate::utils::*;
+
+pub fn configure_batch_auction(ctx: Context<ConfigureBatchAuction>, tick_size: u64) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+
+    require!(
+        presale.sale_mode == SALE_MODE_FIXED_PRICE,
+        IdoError::InvalidSaleMode
+    );
+
+    // Unfilled supply stays with the creator rather than going FCFS
+    require!(!presale.fcfs_enabled, IdoError::InvalidSaleMode);
+
+    // token_price acts as the reserve price for bids
+    require!(presale.token_price > 0, IdoError::InvalidAuctionPrice);
+
+    // The top price level must be representable
+    require!(tick_size > 0, IdoError::InvalidAuctionPrice);
+    let top_price = tick_size
+        .checked_mul(BATCH_PRICE_LEVELS as u64 - 1)
+        .and_then(|range| range.checked_add(presale.token_price))
+        .ok_or(IdoError::InvalidAuctionPrice)?;
+
+    // The book can clear as high as its top level
+    validate_listing_terms(presale, top_price, &ctx.accounts.global_state)?;
+
+    let order_book = &mut ctx.accounts.order_book;
+    order_book.presale = presale.key();
+    order_book.tick_size = tick_size;
+    order_book.level_demand = [0; BATCH_PRICE_LEVELS];
+    order_book.bump = ctx.bumps.order_book;
+
+    presale.sale_mode = SALE_MODE_BATCH_AUCTION;
+
+    msg!("Presale switched to batch auction mode");
+
+    Ok(())
+}
+
+pub fn place_bid(ctx: Context<PlaceBid>, price: u64, quantity: u64) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+
+    let presale = &mut ctx.accounts.presale;
+    let user_info = &mut ctx.accounts.user_info;
+    let bid = &mut ctx.accounts.bid;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.sale_mode == SALE_MODE_BATCH_AUCTION,
+        IdoError::InvalidSaleMode
+    );
+
+    // Ensure presale is within time bounds
+    require!(
+        current_time >= presale.start_time,
+        IdoError::PresaleNotStarted
+    );
+
+    require!(current_time <= presale.end_time, IdoError::PresaleEnded);
+
+    require!(
+        price >= presale.token_price,
+        IdoError::BidBelowReservePrice
+    );
+
+    // Bids must sit on one of the book's price levels
+    let order_book = &mut ctx.accounts.order_book;
+    let offset = price - presale.token_price;
+    let level = offset / order_book.tick_size;
+    require!(
+        level * order_book.tick_size == offset && level < BATCH_PRICE_LEVELS as u64,
+        IdoError::InvalidAuctionPrice
+    );
+    let level = level as usize;
+
+    require!(quantity > 0, IdoError::InsufficientAllocation);
+
+    // Escrow the full bid value until the clearing price is known
+    let deposit = price.checked_mul(quantity).unwrap();
+
+    let presale_key = presale.key();
+    let presale_info = presale.to_account_info();
+
+    invoke(
+        &system_instruction::transfer(&user_key, &presale_key, deposit),
+        &[
+            ctx.accounts.user.to_account_info(),
+            presale_info,
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    bid.bidder = user_key;
+    bid.presale = presale_key;
+    bid.price = price;
+    bid.quantity = quantity;
+    bid.deposit = deposit;
+    bid.settled = false;
+    bid.bump = ctx.bumps.bid;
+
+    order_book.level_demand[level] = order_book.level_demand[level].checked_add(quantity).unwrap();
+    presale.bid_count = presale.bid_count.checked_add(1).unwrap();
+    presale.open_settlements = presale.open_settlements.checked_add(1).unwrap();
+    user_info.sol_committed = user_info.sol_committed.checked_add(deposit).unwrap();
+
+    msg!("User bid for {} tokens at {} lamports", quantity, price);
+
+    Ok(())
+}
+
+/// Walks the book from the highest price level down until supply runs out.
+/// Returns the clearing price, the tokens left for bids at that price and the
+/// tokens bid at it. An undersubscribed book clears at its lowest bid.
+pub fn find_clearing_price(
+    order_book: &BatchOrderBook,
+    reserve_price: u64,
+    supply: u64,
+) -> (u64, u64, u64) {
+    let mut demand_above: u64 = 0;
+    let mut cleared = (reserve_price, 0, 0);
+
+    for (level, &level_demand) in order_book.level_demand.iter().enumerate().rev() {
+        if level_demand == 0 {
+            continue;
+        }
+
+        let level_price = reserve_price + order_book.tick_size * level as u64;
+
+        if demand_above.checked_add(level_demand).unwrap() >= supply {
+            // Marginal level: bids at this price share what is left
+            return (level_price, supply - demand_above, level_demand);
+        }
+
+        demand_above = demand_above.checked_add(level_demand).unwrap();
+
+        // Undersubscribed so far: every bid at this level fills
+        cleared = (level_price, level_demand, level_demand);
+    }
+
+    cleared
+}
+
+/// Permissionless crank. Demand is tallied per price level as bids come in,
+/// so clearing reads one fixed-size book however many bids there are. An
+/// auction not cleared within `BATCH_CLEARING_WINDOW` of its end fails
+/// instead, and every bid can be refunded through refund_bid.
+pub fn compute_clearing_price(ctx: Context<ComputeClearingPrice>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.sale_mode == SALE_MODE_BATCH_AUCTION,
+        IdoError::InvalidSaleMode
+    );
+
+    require!(current_time > presale.end_time, IdoError::PresaleNotCompleted);
+
+    require!(
+        !presale.batch_cleared && !presale.batch_failed,
+        IdoError::AuctionAlreadyCleared
+    );
+
+    if current_time > presale.end_time.checked_add(BATCH_CLEARING_WINDOW).unwrap() {
+        presale.batch_failed = true;
+
+        msg!("Batch auction missed its clearing window, bids can be refunded");
+
+        return Ok(());
+    }
+
+    let (clearing_price, supply_at_clearing, demand_at_clearing) = find_clearing_price(
+        &ctx.accounts.order_book,
+        presale.token_price,
+        presale.tokens_for_sale,
+    );
+
+    presale.clearing_price = clearing_price;
+    presale.batch_supply_at_clearing = supply_at_clearing;
+    presale.batch_demand_at_clearing = demand_at_clearing;
+    presale.batch_cleared = true;
+
+    msg!("Batch auction cleared at {} lamports per token", clearing_price);
+
+    Ok(())
+}
+
+/// Number of tokens a bid receives at the uniform clearing price.
+pub fn calculate_bid_fill(bid: &Bid, presale: &Presale) -> u64 {
+    if bid.price > presale.clearing_price {
+        bid.quantity
+    } else if bid.price == presale.clearing_price && presale.batch_demand_at_clearing > 0 {
+        (bid.quantity as u128)
+            .checked_mul(presale.batch_supply_at_clearing as u128)
+            .unwrap()
+            .checked_div(presale.batch_demand_at_clearing as u128)
+            .unwrap() as u64
+    } else {
+        0
+    }
+}
+
+pub fn settle_bid(ctx: Context<SettleBid>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let user_info = &mut ctx.accounts.user_info;
+    let bid = &mut ctx.accounts.bid;
+
+    require!(presale.batch_cleared, IdoError::AuctionNotCleared);
+
+    require!(!bid.settled, IdoError::AlreadySettled);
+
+    let filled = calculate_bid_fill(bid, presale);
+    let cost = filled.checked_mul(presale.clearing_price).unwrap();
+    let refund = bid.deposit.checked_sub(cost).unwrap();
+
+    // Over-payments and losing bids are refunded
+    if refund > 0 {
+        transfer_lamports_from_pda(
+            &presale.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            refund,
+        )?;
+    }
+
+    presale.tokens_sold = presale.tokens_sold.checked_add(filled).unwrap();
+    presale.sol_raised = presale.sol_raised.checked_add(cost).unwrap();
+
+    // Won tokens are released through claim_tokens
+    user_info.allocation = user_info.allocation.checked_add(filled).unwrap();
+    user_info.purchased = user_info.purchased.checked_add(filled).unwrap();
+    user_info.settled = true;
+    presale.open_settlements = presale.open_settlements.saturating_sub(1);
+
+    bid.settled = true;
+
+    msg!(
+        "Bid settled for {} tokens and refunded {} lamports",
+        filled,
+        refund
+    );
+
+    Ok(())
+}
+
+/// Returns the whole deposit of a bid in an auction that failed to clear.
+pub fn refund_bid(ctx: Context<SettleBid>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let user_info = &mut ctx.accounts.user_info;
+    let bid = &mut ctx.accounts.bid;
+
+    require!(presale.batch_failed, IdoError::AuctionNotCleared);
+
+    require!(!bid.settled, IdoError::AlreadySettled);
+
+    transfer_lamports_from_pda(
+        &presale.to_account_info(),
+        &ctx.accounts.user.to_account_info(),
+        bid.deposit,
+    )?;
+
+    user_info.settled = true;
+    bid.settled = true;
+    presale.open_settlements = presale.open_settlements.saturating_sub(1);
+
+    msg!("Bid refunded {} lamports", bid.deposit);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ConfigureBatchAuction<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.creator == creator.key() @ IdoError::Unauthorized,
+        constraint = presale.status == STATUS_PENDING @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + BatchOrderBook::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_ORDER_BOOK,
+            presale.key().as_ref(),
+        ],
+        bump
+    )]
+    pub order_book: Account<'info, BatchOrderBook>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_LIVE @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_INFO,
+            user.key().as_ref(),
+            presale.key().as_ref(),
+        ],
+        bump = user_info.bump,
+        constraint = user_info.user == user.key(),
+        constraint = user_info.presale == presale.key()
+    )]
+    pub user_info: Account<'info, UserPresaleInfo>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_ORDER_BOOK,
+            presale.key().as_ref(),
+        ],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, BatchOrderBook>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + Bid::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_BID,
+            presale.key().as_ref(),
+            user.key().as_ref(),
+        ],
+        bump
+    )]
+    pub bid: Account<'info, Bid>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ComputeClearingPrice<'info> {
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_LIVE || presale.status == STATUS_COMPLETED @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        seeds = [
+            SEED_PREFIX_ORDER_BOOK,
+            presale.key().as_ref(),
+        ],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, BatchOrderBook>,
+}
+
+// Settling or refunding closes the bid, returning its rent to the bidder
+#[derive(Accounts)]
+pub struct SettleBid<'info> {
+    /// CHECK: Receives the refund and the bid's rent, and is tied to the
+    /// user info by its seeds. Settling is permissionless so the protocol
+    /// fee isn't held up by users who never settle.
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            SEED_PREFIX_BID,
+            presale.key().as_ref(),
+            user.key().as_ref(),
+        ],
+        bump = bid.bump,
+        constraint = bid.bidder == user.key()
+    )]
+    pub bid: Account<'info, Bid>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_INFO,
+            user.key().as_ref(),
+            presale.key().as_ref(),
+        ],
+        bump = user_info.bump,
+        constraint = user_info.user == user.key(),
+        constraint = user_info.presale == presale.key()
+    )]
+    pub user_info: Account<'info, UserPresaleInfo>,
+}
@@ -0,0 +1,325 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::presale::{validate_listing_terms, ConfigurePresale};
+use crate::state::*;
+use crate::utils::*;
+
+pub fn configure_bonding_curve(
+    ctx: Context<ConfigurePresale>,
+    curve_type: u8,
+    curve_base_price: u64,
+    curve_step_size: u64,
+    curve_step_increment: u64,
+    curve_graduation_threshold: u64,
+) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+
+    require!(
+        presale.sale_mode == SALE_MODE_FIXED_PRICE,
+        IdoError::InvalidSaleMode
+    );
+
+    require!(!presale.fcfs_enabled, IdoError::InvalidSaleMode);
+
+    require!(
+        curve_type == CURVE_TYPE_LINEAR || curve_type == CURVE_TYPE_EXPONENTIAL,
+        IdoError::InvalidCurveParameters
+    );
+
+    require!(
+        curve_base_price > 0 && curve_step_size > 0 && curve_step_increment > 0,
+        IdoError::InvalidCurveParameters
+    );
+
+    require!(
+        curve_graduation_threshold > 0,
+        IdoError::InvalidCurveParameters
+    );
+
+    presale.sale_mode = SALE_MODE_BONDING_CURVE;
+    presale.curve_type = curve_type;
+    presale.curve_base_price = curve_base_price;
+    presale.curve_step_size = curve_step_size;
+    presale.curve_step_increment = curve_step_increment;
+    presale.curve_graduation_threshold = curve_graduation_threshold;
+    presale.token_price = curve_base_price;
+
+    // Fail now rather than on the last buy if the full supply can't be priced
+    let total_cost = calculate_curve_integral(presale, presale.tokens_for_sale)?;
+
+    // The last token sold is the most expensive
+    let last_token_price = total_cost
+        .checked_sub(calculate_curve_integral(presale, presale.tokens_for_sale.saturating_sub(1))?)
+        .unwrap();
+    validate_listing_terms(presale, last_token_price, &ctx.accounts.global_state)?;
+
+    msg!("Presale switched to bonding curve mode");
+
+    Ok(())
+}
+
+/// Fixed-point exponentiation by squaring, scaled by `CURVE_PRECISION`.
+fn pow_fixed(base: u128, mut exponent: u64) -> Result<u128> {
+    let mut result = CURVE_PRECISION;
+    let mut base = base;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result
+                .checked_mul(base)
+                .ok_or(IdoError::MathOverflow)?
+                / CURVE_PRECISION;
+        }
+
+        exponent >>= 1;
+
+        if exponent > 0 {
+            base = base.checked_mul(base).ok_or(IdoError::MathOverflow)? / CURVE_PRECISION;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Total lamports paid for the first `supply` tokens on the curve.
+///
+/// The price is a step function: every `curve_step_size` tokens it rises by
+/// `curve_step_increment` lamports (linear) or by `curve_step_increment` bps
+/// of the previous step (exponential).
+pub fn calculate_curve_integral(presale: &Presale, supply: u64) -> Result<u64> {
+    let base = presale.curve_base_price as u128;
+    let step = presale.curve_step_size as u128;
+    let increment = presale.curve_step_increment as u128;
+
+    let steps = supply as u128 / step;
+    let remainder = supply as u128 % step;
+
+    let total = if presale.curve_type == CURVE_TYPE_EXPONENTIAL {
+        let growth = CURVE_PRECISION
+            .checked_mul(BPS_DENOMINATOR as u128 + increment)
+            .ok_or(IdoError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        let growth_pow = pow_fixed(growth, steps as u64)?;
+
+        // base * step * (g^k - 1) / (g - 1) for the completed steps
+        let full_steps = base
+            .checked_mul(step)
+            .and_then(|v| v.checked_mul(growth_pow - CURVE_PRECISION))
+            .ok_or(IdoError::MathOverflow)?
+            / (growth - CURVE_PRECISION);
+
+        // The partial step is priced at base * g^k
+        let partial_step = remainder
+            .checked_mul(base)
+            .and_then(|v| v.checked_mul(growth_pow))
+            .ok_or(IdoError::MathOverflow)?
+            / CURVE_PRECISION;
+
+        full_steps.checked_add(partial_step).ok_or(IdoError::MathOverflow)?
+    } else {
+        // step * (k * base + increment * k * (k - 1) / 2) for the completed steps
+        let arithmetic_sum = steps
+            .checked_mul(base)
+            .and_then(|v| {
+                increment
+                    .checked_mul(steps)
+                    .and_then(|i| i.checked_mul(steps.saturating_sub(1)))
+                    .and_then(|i| v.checked_add(i / 2))
+            })
+            .ok_or(IdoError::MathOverflow)?;
+        let full_steps = arithmetic_sum
+            .checked_mul(step)
+            .ok_or(IdoError::MathOverflow)?;
+
+        // The partial step is priced at base + k * increment
+        let partial_step = increment
+            .checked_mul(steps)
+            .and_then(|v| v.checked_add(base))
+            .and_then(|v| v.checked_mul(remainder))
+            .ok_or(IdoError::MathOverflow)?;
+
+        full_steps.checked_add(partial_step).ok_or(IdoError::MathOverflow)?
+    };
+
+    u64::try_from(total).map_err(|_| error!(IdoError::MathOverflow))
+}
+
+pub fn buy_from_curve(ctx: Context<CurveTrade>, amount: u64, max_sol_cost: u64) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+
+    let presale = &mut ctx.accounts.presale;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.sale_mode == SALE_MODE_BONDING_CURVE,
+        IdoError::InvalidSaleMode
+    );
+
+    // Ensure presale is within time bounds
+    require!(
+        current_time >= presale.start_time,
+        IdoError::PresaleNotStarted
+    );
+
+    require!(current_time <= presale.end_time, IdoError::PresaleEnded);
+
+    let new_supply = presale.tokens_sold.checked_add(amount).unwrap();
+    require!(
+        amount > 0 && new_supply <= presale.tokens_for_sale,
+        IdoError::InsufficientTokensRemaining
+    );
+
+    let sol_cost = calculate_curve_integral(presale, new_supply)?
+        .checked_sub(calculate_curve_integral(presale, presale.tokens_sold)?)
+        .unwrap();
+
+    require!(sol_cost <= max_sol_cost, IdoError::SlippageExceeded);
+
+    let presale_key = presale.key();
+    let presale_info = presale.to_account_info();
+
+    // Transfer SOL from user to presale account
+    invoke(
+        &system_instruction::transfer(&user_key, &presale_key, sol_cost),
+        &[
+            ctx.accounts.user.to_account_info(),
+            presale_info.clone(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    // Curve buyers receive their tokens right away
+    let presale_id = presale.id_seed();
+    let seeds = &[
+        SEED_PREFIX_PRESALE,
+        presale.mint_of_token_being_sold.as_ref(),
+        presale.creator.as_ref(),
+        presale_id.as_ref(),
+        &[presale.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.presale_token_account.to_account_info(),
+        to: ctx.accounts.user_token_account.to_account_info(),
+        authority: presale_info,
+    };
+
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+
+    token::transfer(
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+        amount,
+    )?;
+
+    presale.tokens_sold = new_supply;
+    presale.sol_raised = presale.sol_raised.checked_add(sol_cost).unwrap();
+
+    // Graduate to listing once the threshold is reached; no more trades
+    if presale.sol_raised >= presale.curve_graduation_threshold {
+        set_presale_status(presale, &mut ctx.accounts.global_state, STATUS_COMPLETED);
+        msg!("Bonding curve graduated, token is ready to be listed");
+    }
+
+    msg!("User bought {} tokens from the curve for {} lamports", amount, sol_cost);
+
+    Ok(())
+}
+
+/// Sells tokens back at the curve price. Unlike buying, this stays open
+/// after `end_time` until the curve graduates, so holders of a curve that
+/// never reaches its threshold can always exit.
+pub fn sell_to_curve(ctx: Context<CurveTrade>, amount: u64, min_sol_out: u64) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.sale_mode == SALE_MODE_BONDING_CURVE,
+        IdoError::InvalidSaleMode
+    );
+
+    require!(
+        current_time >= presale.start_time,
+        IdoError::PresaleNotStarted
+    );
+
+    require!(
+        amount > 0 && amount <= presale.tokens_sold,
+        IdoError::InsufficientAllocation
+    );
+
+    let new_supply = presale.tokens_sold.checked_sub(amount).unwrap();
+    let sol_out = calculate_curve_integral(presale, presale.tokens_sold)?
+        .checked_sub(calculate_curve_integral(presale, new_supply)?)
+        .unwrap();
+
+    require!(sol_out >= min_sol_out, IdoError::SlippageExceeded);
+
+    // Return the tokens to the curve
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.user_token_account.to_account_info(),
+        to: ctx.accounts.presale_token_account.to_account_info(),
+        authority: ctx.accounts.user.to_account_info(),
+    };
+
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+    transfer_lamports_from_pda(
+        &presale.to_account_info(),
+        &ctx.accounts.user.to_account_info(),
+        sol_out,
+    )?;
+
+    presale.tokens_sold = new_supply;
+    presale.sol_raised = presale.sol_raised.checked_sub(sol_out).unwrap();
+
+    msg!("User sold {} tokens to the curve for {} lamports", amount, sol_out);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CurveTrade<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_LIVE @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        constraint = presale_token_account.key() == presale.presale_token_account
+    )]
+    pub presale_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key(),
+        constraint = user_token_account.mint == presale.mint_of_token_being_sold
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
@@ -2,12 +2,38 @@ pub const TIER_1_REQUIREMENT: u64 = 1_000;
 pub const TIER_2_REQUIREMENT: u64 = 10_000;
 pub const TIER_3_REQUIREMENT: u64 = 100_000;
 
+pub const LOYALTY_FLASH_STAKE_DURATION: i64 = 86_400; // 1 day in seconds
+pub const LOYALTY_SHORT_TERM_DURATION: i64 = 2_592_000; // 30 days in seconds
+pub const LOYALTY_MEDIUM_TERM_DURATION: i64 = 7_776_000; // 90 days in seconds
+pub const LOYALTY_LONG_TERM_DURATION: i64 = 15_552_000; // 180 days in seconds
+
+pub const LOYALTY_FLASH_STAKE_MULTIPLIER_BPS: u64 = 5_000;
+pub const LOYALTY_BASE_MULTIPLIER_BPS: u64 = 10_000;
+pub const LOYALTY_SHORT_TERM_MULTIPLIER_BPS: u64 = 12_500;
+pub const LOYALTY_MEDIUM_TERM_MULTIPLIER_BPS: u64 = 15_000;
+pub const LOYALTY_LONG_TERM_MULTIPLIER_BPS: u64 = 20_000;
+
+pub const LOCK_TERM_FLEXIBLE: u8 = 0;
+pub const LOCK_TERM_30_DAYS: u8 = 1;
+pub const LOCK_TERM_90_DAYS: u8 = 2;
+pub const LOCK_TERM_180_DAYS: u8 = 3;
+
+pub const LOCK_TERM_30_DAYS_DURATION: i64 = 2_592_000; // 30 days in seconds
+pub const LOCK_TERM_90_DAYS_DURATION: i64 = 7_776_000; // 90 days in seconds
+pub const LOCK_TERM_180_DAYS_DURATION: i64 = 15_552_000; // 180 days in seconds
+
+pub const LOCK_TERM_FLEXIBLE_WEIGHT_BPS: u16 = 10_000;
+pub const LOCK_TERM_30_DAYS_WEIGHT_BPS: u16 = 11_000;
+pub const LOCK_TERM_90_DAYS_WEIGHT_BPS: u16 = 12_500;
+pub const LOCK_TERM_180_DAYS_WEIGHT_BPS: u16 = 15_000;
+
 pub const TIER_1_ALLOCATION_PERCENTAGE: u8 = 34;
 pub const TIER_2_ALLOCATION_PERCENTAGE: u8 = 33;
 pub const TIER_3_ALLOCATION_PERCENTAGE: u8 = 33;
 
 pub const PRESALE_CREATOR_WITHDRAWAL_PERCENTAGE: u8 = 30;
 pub const LIQUIDITY_PERCENTAGE: u8 = 60;
+pub const LISTING_LIQUIDITY_PERCENTAGE: u8 = 80; // Share of the raise list_token pairs with the reserve
 pub const PROTOCOL_FEE_PERCENTAGE: u8 = 10;
 
 pub const VESTING_FIRST_RELEASE_PERCENTAGE: u8 = 40;
@@ -20,11 +46,58 @@ pub const VESTING_THIRD_RELEASE_DURATION: i64 = 900; // 15 minutes in seconds
 
 pub const SEED_PREFIX_PRESALE: &[u8] = b"presale";
 pub const SEED_PREFIX_USER_STAKE: &[u8] = b"user_stake";
+pub const SEED_PREFIX_STAKE_SUMMARY: &[u8] = b"stake_summary";
+pub const SEED_PREFIX_STAKING_MINT: &[u8] = b"staking_mint";
 pub const SEED_PREFIX_USER_INFO: &[u8] = b"user_info";
 pub const SEED_PREFIX_VESTING: &[u8] = b"vesting";
+pub const SEED_PREFIX_BID: &[u8] = b"bid";
+pub const SEED_PREFIX_ORDER_BOOK: &[u8] = b"order_book";
+pub const SEED_PREFIX_STAKING_POOL: &[u8] = b"staking_pool";
+pub const SEED_PREFIX_PRESALE_METADATA: &[u8] = b"presale_metadata";
+pub const SEED_PREFIX_PROJECT: &[u8] = b"project";
+pub const SEED_PREFIX_ALLOWLIST: &[u8] = b"allowlist";
 
 pub const STATUS_PENDING: u8 = 0;
 pub const STATUS_APPROVED: u8 = 1;
 pub const STATUS_LIVE: u8 = 2;
 pub const STATUS_COMPLETED: u8 = 3;
-pub const STATUS_CANCELLED: u8 = 4;
\ No newline at end of file
+pub const STATUS_CANCELLED: u8 = 4;
+
+// Presale metadata limits
+pub const METADATA_MAX_NAME_LEN: usize = 32;
+pub const METADATA_MAX_SYMBOL_LEN: usize = 10;
+pub const METADATA_MAX_URI_LEN: usize = 200;
+
+// Layout version written to newly created or migrated accounts
+pub const ACCOUNT_VERSION: u8 = 2;
+
+// Counter array sizes for protocol stats
+pub const PRESALE_STATUS_COUNT: usize = 5;
+pub const TIER_COUNT: usize = 4;
+
+// Keeps every position of a user within one transaction when slashing
+pub const MAX_STAKE_POSITIONS: u32 = 6;
+
+pub const SALE_MODE_FIXED_PRICE: u8 = 0;
+pub const SALE_MODE_OVERFLOW: u8 = 1;
+pub const SALE_MODE_DUTCH_AUCTION: u8 = 2;
+pub const SALE_MODE_BATCH_AUCTION: u8 = 3;
+pub const SALE_MODE_BONDING_CURVE: u8 = 4;
+
+// Batch auction bids sit on a grid of price levels above the reserve price
+pub const BATCH_PRICE_LEVELS: usize = 64;
+pub const BATCH_CLEARING_WINDOW: i64 = 604_800; // 7 days in seconds to clear before bids are refunded
+
+pub const CURVE_TYPE_LINEAR: u8 = 0;
+pub const CURVE_TYPE_EXPONENTIAL: u8 = 1;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const CURVE_PRECISION: u128 = 1_000_000_000_000;
+
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+pub const SOL_REWARD_DURATION: i64 = 604_800; // Fee revenue streams to stakers over 7 days
+
+// LP valuation moves towards the pool's spot rate in bounded steps
+pub const LP_RATE_PRECISION: u128 = 1_000_000_000_000;
+pub const LP_RATE_UPDATE_INTERVAL: i64 = 3_600; // 1 hour in seconds
+pub const LP_RATE_MAX_CHANGE_BPS: u64 = 500;
\ No newline at end of file
@@ -0,0 +1,163 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::*;
+use crate::presale::{validate_listing_terms, ConfigurePresale};
+use crate::state::*;
+use crate::utils::*;
+
+pub fn configure_dutch_auction(
+    ctx: Context<ConfigurePresale>,
+    auction_start_price: u64,
+    auction_floor_price: u64,
+) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+
+    require!(
+        presale.sale_mode == SALE_MODE_FIXED_PRICE,
+        IdoError::InvalidSaleMode
+    );
+
+    // Leftovers are settled at the clearing price, not sold FCFS
+    require!(!presale.fcfs_enabled, IdoError::InvalidSaleMode);
+
+    require!(auction_floor_price > 0, IdoError::InvalidAuctionPrice);
+
+    require!(
+        auction_start_price > auction_floor_price,
+        IdoError::InvalidAuctionPrice
+    );
+
+    presale.sale_mode = SALE_MODE_DUTCH_AUCTION;
+    presale.auction_start_price = auction_start_price;
+    presale.auction_floor_price = auction_floor_price;
+
+    // The floor is the lowest price the sale can ever clear at
+    presale.token_price = auction_floor_price;
+
+    // Early buyers can pay up to the start price
+    validate_listing_terms(presale, auction_start_price, &ctx.accounts.global_state)?;
+
+    msg!("Presale switched to Dutch auction mode");
+
+    Ok(())
+}
+
+/// Price decays linearly from the start price at `start_time` to the floor
+/// price at `end_time`.
+pub fn get_current_auction_price(presale: &Presale, current_time: i64) -> u64 {
+    if current_time <= presale.start_time {
+        return presale.auction_start_price;
+    }
+
+    if current_time >= presale.end_time {
+        return presale.auction_floor_price;
+    }
+
+    let price_range = presale
+        .auction_start_price
+        .checked_sub(presale.auction_floor_price)
+        .unwrap() as u128;
+    let elapsed = (current_time - presale.start_time) as u128;
+    let duration = (presale.end_time - presale.start_time) as u128;
+
+    let decay = price_range
+        .checked_mul(elapsed)
+        .unwrap()
+        .checked_div(duration)
+        .unwrap() as u64;
+
+    presale.auction_start_price.checked_sub(decay).unwrap()
+}
+
+/// A sold-out auction clears at the price paid by the last buyer; otherwise
+/// everyone pays the floor price.
+pub fn get_auction_clearing_price(presale: &Presale) -> u64 {
+    if presale.tokens_sold >= presale.tokens_for_sale {
+        presale.auction_last_price
+    } else {
+        presale.auction_floor_price
+    }
+}
+
+pub fn settle_dutch_auction(ctx: Context<SettleDutchAuction>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let user_info = &mut ctx.accounts.user_info;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.sale_mode == SALE_MODE_DUTCH_AUCTION,
+        IdoError::InvalidSaleMode
+    );
+
+    // The clearing price is only final once the window is closed
+    require!(current_time > presale.end_time, IdoError::PresaleNotCompleted);
+
+    require!(!user_info.settled, IdoError::AlreadySettled);
+
+    require!(user_info.sol_committed > 0, IdoError::NothingToSettle);
+
+    let clearing_price = get_auction_clearing_price(presale);
+    let cost = user_info.purchased.checked_mul(clearing_price).unwrap();
+    let refund = user_info.sol_committed.checked_sub(cost).unwrap();
+
+    // Refund the difference between what was paid and the clearing price
+    if refund > 0 {
+        transfer_lamports_from_pda(
+            &presale.to_account_info(),
+            &ctx.accounts.payer.to_account_info(),
+            refund,
+        )?;
+    }
+
+    presale.sol_raised = presale.sol_raised.checked_sub(refund).unwrap();
+    presale.open_settlements = presale.open_settlements.saturating_sub(1);
+    user_info.settled = true;
+
+    msg!(
+        "User settled at clearing price {} and was refunded {} lamports",
+        clearing_price,
+        refund
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettleDutchAuction<'info> {
+    /// CHECK: Tied to the user info by its seeds. Settling is permissionless
+    /// so the protocol fee isn't held up by users who never settle.
+    pub user: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the refund; the user or the delegate who paid
+    #[account(
+        mut,
+        constraint = payer.key() == user_info.refund_recipient() @ IdoError::Unauthorized
+    )]
+    pub payer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_LIVE || presale.status == STATUS_COMPLETED @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_INFO,
+            user.key().as_ref(),
+            presale.key().as_ref(),
+        ],
+        bump = user_info.bump,
+        constraint = user_info.user == user.key(),
+        constraint = user_info.presale == presale.key()
+    )]
+    pub user_info: Account<'info, UserPresaleInfo>,
+}
@@ -52,4 +52,142 @@ pub enum IdoError {
 
     #[msg("Token has been listed to the market already")]
     TokenAlreadyListed,
+
+    #[msg("FCFS round is not enabled for this presale")]
+    FcfsNotEnabled,
+
+    #[msg("FCFS round not started yet")]
+    FcfsNotStarted,
+
+    #[msg("FCFS round already ended")]
+    FcfsEnded,
+
+    #[msg("FCFS per-wallet cap exceeded")]
+    FcfsWalletCapExceeded,
+
+    #[msg("Not enough unsold tokens left in the presale")]
+    InsufficientTokensRemaining,
+
+    #[msg("Instruction is not available in this sale mode")]
+    InvalidSaleMode,
+
+    #[msg("Commitment already settled")]
+    AlreadySettled,
+
+    #[msg("Nothing to settle")]
+    NothingToSettle,
+
+    #[msg("Auction prices are invalid")]
+    InvalidAuctionPrice,
+
+    #[msg("Bid price is below the reserve price")]
+    BidBelowReservePrice,
+
+    #[msg("Every bid account must be supplied exactly once")]
+    InvalidBidAccounts,
+
+    #[msg("Batch auction clearing price already computed")]
+    AuctionAlreadyCleared,
+
+    #[msg("Batch auction clearing price not computed yet")]
+    AuctionNotCleared,
+
+    #[msg("Bonding curve parameters are invalid")]
+    InvalidCurveParameters,
+
+    #[msg("Price moved beyond the allowed slippage")]
+    SlippageExceeded,
+
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+
+    #[msg("Basis points value is out of range")]
+    InvalidBasisPoints,
+
+    #[msg("Protocol fee already collected")]
+    ProtocolFeeAlreadyCollected,
+
+    #[msg("Invalid lock term")]
+    InvalidLockTerm,
+
+    #[msg("Stake is locked until the end of its lock term")]
+    StakeLocked,
+
+    #[msg("Invalid stake position index")]
+    InvalidPositionIndex,
+
+    #[msg("Every stake position must be supplied exactly once")]
+    InvalidStakePositions,
+
+    #[msg("Stake summary is out of date and must be refreshed")]
+    StakeSummaryStale,
+
+    #[msg("Staking mint is not accepted")]
+    StakingMintNotAccepted,
+
+    #[msg("LP reserve account is missing or does not match the staking mint config")]
+    InvalidLpReserveAccount,
+
+    #[msg("Tier eligibility is on cooldown after a missed presale")]
+    TierCooldownActive,
+
+    #[msg("No-show penalty is not configured for this presale")]
+    NoShowPenaltyDisabled,
+
+    #[msg("User participated in the presale or was already penalized")]
+    NotANoShow,
+
+    #[msg("Accounts required to slash the stake are missing or invalid")]
+    InvalidSlashAccounts,
+
+    #[msg("User still has tokens to claim or SOL to settle")]
+    UserInfoNotSettled,
+
+    #[msg("Stake position still holds tokens or unclaimed rewards")]
+    StakePositionNotEmpty,
+
+    #[msg("Presale still has open registrations, unlisted tokens or uncollected fees")]
+    PresaleNotClosable,
+
+    #[msg("Account is not owned by this program or has the wrong type")]
+    InvalidMigrationAccount,
+
+    #[msg("Account already uses the current layout")]
+    AccountAlreadyMigrated,
+
+    #[msg("Presale metadata field exceeds its maximum length")]
+    MetadataTooLong,
+
+    #[msg("Round overlaps the previous round of the project")]
+    RoundOverlap,
+
+    #[msg("Round supply exceeds the tokens deposited in the project")]
+    ProjectSupplyExceeded,
+
+    #[msg("User is not on the allowlist for this round")]
+    NotOnAllowlist,
+
+    #[msg("Application fee accounts are missing or invalid")]
+    InvalidFeeAccounts,
+
+    #[msg("Listing price is outside the allowed band around the sale price")]
+    InvalidListingPrice,
+
+    #[msg("LP rate was updated too recently")]
+    LpRateUpdateTooEarly,
+
+    #[msg("Presale still has unsettled commitments")]
+    SettlementsPending,
+
+    #[msg("Liquidity reserve is too small to list at the listing price")]
+    InsufficientLiquidityReserve,
+
+    #[msg("Maximum number of stake positions reached")]
+    TooManyStakePositions,
+
+    #[msg("Auction purchases must be paid by the wallet that made the first one")]
+    AuctionPayerMismatch,
+
+    #[msg("Bonding curve sales only complete by graduating")]
+    CurveNotGraduated,
 }
\ No newline at end of file
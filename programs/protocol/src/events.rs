@@ -0,0 +1,9 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct PresaleRejected {
+    pub presale: Pubkey,
+    pub creator: Pubkey,
+    pub presale_id: u64,
+    pub reason: u8,                         // Reason code chosen by the admin
+}
@@ -4,17 +4,37 @@ use anchor_lang::prelude::*;
 // automatically when you build the project.
 declare_id!("4SF1zHVgcXfhbp1KCxuRPNQzurfML6XvQuVERnEnAWU8");
 
+pub mod batch_auction;
+pub mod bonding_curve;
 pub mod constants;
+pub mod dutch_auction;
 pub mod errors;
+pub mod events;
+pub mod metadata;
+pub mod migration;
+pub mod overflow;
+pub mod penalty;
 pub mod presale;
+pub mod project;
+pub mod rewards;
 pub mod staking;
 pub mod state;
 pub mod tier;
 pub mod utils;
 pub mod vesting;
 
+use batch_auction::*;
+use bonding_curve::*;
+use dutch_auction::*;
+use metadata::*;
+use migration::*;
+use overflow::*;
+use penalty::*;
 use presale::*;
+use project::*;
+use rewards::*;
 use staking::*;
+use state::{CreatePresaleParams, PresaleMetadataArgs, ProtocolStats, UpdatePresaleParams};
 use utils::*;
 use vesting::*;
 
@@ -34,35 +54,92 @@ mod sfund_sonic {
         utils::update_admin(ctx, new_admin)
     }
 
+    pub fn set_staker_fee_share(
+        ctx: Context<UpdateGlobalConfig>,
+        staker_fee_share_bps: u16,
+    ) -> Result<()> {
+        utils::set_staker_fee_share(ctx, staker_fee_share_bps)
+    }
+
+    pub fn set_application_fees(
+        ctx: Context<UpdateGlobalConfig>,
+        application_fee: u64,
+        application_fee_in_staking_token: bool,
+        listing_deposit: u64,
+    ) -> Result<()> {
+        utils::set_application_fees(
+            ctx,
+            application_fee,
+            application_fee_in_staking_token,
+            listing_deposit,
+        )
+    }
+
+    pub fn set_listing_price_band(
+        ctx: Context<UpdateGlobalConfig>,
+        listing_band_min_bps: u16,
+        listing_band_max_bps: u16,
+    ) -> Result<()> {
+        utils::set_listing_price_band(ctx, listing_band_min_bps, listing_band_max_bps)
+    }
+
+    pub fn get_protocol_stats(ctx: Context<GetProtocolStats>) -> Result<ProtocolStats> {
+        utils::get_protocol_stats(ctx)
+    }
+
+    // Migration functions
+    pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> Result<()> {
+        migration::migrate_global_state(ctx)
+    }
+
+    pub fn migrate_presale(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate_presale(ctx)
+    }
+
+    pub fn migrate_user_stake(ctx: Context<MigrateUserStake>) -> Result<()> {
+        migration::migrate_user_stake(ctx)
+    }
+
+    pub fn migrate_user_presale_info(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate_user_presale_info(ctx)
+    }
+
     // Presale functions
     pub fn create_presale(
         ctx: Context<CreatePresale>,
-        tokens_for_sale: u64,
-        token_price: u64,
-        start_time: i64,
-        end_time: i64,
-        registration_start_time: i64,
-        registration_end_time: i64,
-        listing_price: u64,
-        vesting_enabled: bool,
-    ) -> Result<()> {
-        presale::create_presale(
-            ctx,
-            tokens_for_sale,
-            token_price,
-            start_time,
-            end_time,
-            registration_start_time,
-            registration_end_time,
-            listing_price,
-            vesting_enabled,
-        )
+        params: CreatePresaleParams,
+        metadata: PresaleMetadataArgs,
+    ) -> Result<()> {
+        presale::create_presale(ctx, params, metadata)
+    }
+
+    pub fn update_presale(ctx: Context<UpdatePresale>, params: UpdatePresaleParams) -> Result<()> {
+        presale::update_presale(ctx, params)
+    }
+
+    pub fn update_presale_metadata(
+        ctx: Context<UpdatePresaleMetadata>,
+        metadata: PresaleMetadataArgs,
+    ) -> Result<()> {
+        metadata::update_presale_metadata(ctx, metadata)
     }
 
     pub fn approve_presale(ctx: Context<ApprovePresale>) -> Result<()> {
         presale::approve_presale(ctx)
     }
 
+    pub fn reject_presale(ctx: Context<RejectPresale>, reason: u8) -> Result<()> {
+        presale::reject_presale(ctx, reason)
+    }
+
+    pub fn start_presale(ctx: Context<UpdatePresaleStatus>) -> Result<()> {
+        presale::start_presale(ctx)
+    }
+
+    pub fn finalize_presale(ctx: Context<UpdatePresaleStatus>) -> Result<()> {
+        presale::finalize_presale(ctx)
+    }
+
     pub fn register_for_presale(ctx: Context<RegisterForPresale>) -> Result<()> {
         presale::register_for_presale(ctx)
     }
@@ -71,20 +148,235 @@ mod sfund_sonic {
         presale::buy_tokens(ctx, amount)
     }
 
+    pub fn configure_fcfs(
+        ctx: Context<ConfigureFcfs>,
+        fcfs_start_time: i64,
+        fcfs_end_time: i64,
+        fcfs_wallet_cap: u64,
+    ) -> Result<()> {
+        presale::configure_fcfs(ctx, fcfs_start_time, fcfs_end_time, fcfs_wallet_cap)
+    }
+
+    pub fn buy_tokens_fcfs(ctx: Context<BuyTokensFcfs>, amount: u64) -> Result<()> {
+        presale::buy_tokens_fcfs(ctx, amount)
+    }
+
     pub fn list_token(ctx: Context<ListToken>) -> Result<()> {
         presale::list_token(ctx)
     }
 
+    pub fn collect_protocol_fee(ctx: Context<CollectProtocolFee>) -> Result<()> {
+        presale::collect_protocol_fee(ctx)
+    }
+
+    pub fn close_user_presale_info(ctx: Context<CloseUserPresaleInfo>) -> Result<()> {
+        presale::close_user_presale_info(ctx)
+    }
+
+    pub fn close_presale(ctx: Context<ClosePresale>) -> Result<()> {
+        presale::close_presale(ctx)
+    }
+
+    pub fn configure_no_show_penalty(
+        ctx: Context<ManagePresale>,
+        no_show_cooldown: i64,
+        no_show_slash_bps: u16,
+    ) -> Result<()> {
+        penalty::configure_no_show_penalty(ctx, no_show_cooldown, no_show_slash_bps)
+    }
+
+    pub fn penalize_no_show<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PenalizeNoShow<'info>>,
+    ) -> Result<()> {
+        penalty::penalize_no_show(ctx)
+    }
+
+    // Project functions
+    pub fn deposit_project_tokens(ctx: Context<DepositProjectTokens>, amount: u64) -> Result<()> {
+        project::deposit_project_tokens(ctx, amount)
+    }
+
+    pub fn configure_round_vesting(
+        ctx: Context<ConfigurePresale>,
+        vesting_percentages: [u8; 3],
+        release_delays: [i64; 3],
+    ) -> Result<()> {
+        project::configure_round_vesting(ctx, vesting_percentages, release_delays)
+    }
+
+    pub fn configure_allowlist(ctx: Context<ConfigurePresale>, allowlist_enabled: bool) -> Result<()> {
+        project::configure_allowlist(ctx, allowlist_enabled)
+    }
+
+    pub fn add_to_allowlist(
+        ctx: Context<AddToAllowlist>,
+        user: Pubkey,
+        allocation: u64,
+    ) -> Result<()> {
+        project::add_to_allowlist(ctx, user, allocation)
+    }
+
+    // Overflow sale functions
+
+    pub fn configure_overflow_sale(ctx: Context<ConfigurePresale>) -> Result<()> {
+        overflow::configure_overflow_sale(ctx)
+    }
+
+    pub fn commit_sol(ctx: Context<CommitSol>, amount: u64) -> Result<()> {
+        overflow::commit_sol(ctx, amount)
+    }
+
+    pub fn settle_overflow(ctx: Context<SettleOverflow>) -> Result<()> {
+        overflow::settle_overflow(ctx)
+    }
+
+    // Dutch auction functions
+
+    pub fn configure_dutch_auction(
+        ctx: Context<ConfigurePresale>,
+        auction_start_price: u64,
+        auction_floor_price: u64,
+    ) -> Result<()> {
+        dutch_auction::configure_dutch_auction(ctx, auction_start_price, auction_floor_price)
+    }
+
+    pub fn settle_dutch_auction(ctx: Context<SettleDutchAuction>) -> Result<()> {
+        dutch_auction::settle_dutch_auction(ctx)
+    }
+
+    // Batch auction functions
+
+    pub fn configure_batch_auction(
+        ctx: Context<ConfigureBatchAuction>,
+        tick_size: u64,
+    ) -> Result<()> {
+        batch_auction::configure_batch_auction(ctx, tick_size)
+    }
+
+    pub fn place_bid(ctx: Context<PlaceBid>, price: u64, quantity: u64) -> Result<()> {
+        batch_auction::place_bid(ctx, price, quantity)
+    }
+
+    pub fn compute_clearing_price(ctx: Context<ComputeClearingPrice>) -> Result<()> {
+        batch_auction::compute_clearing_price(ctx)
+    }
+
+    pub fn settle_bid(ctx: Context<SettleBid>) -> Result<()> {
+        batch_auction::settle_bid(ctx)
+    }
+
+    pub fn refund_bid(ctx: Context<SettleBid>) -> Result<()> {
+        batch_auction::refund_bid(ctx)
+    }
+
+    // Bonding curve functions
+
+    pub fn configure_bonding_curve(
+        ctx: Context<ConfigurePresale>,
+        curve_type: u8,
+        curve_base_price: u64,
+        curve_step_size: u64,
+        curve_step_increment: u64,
+        curve_graduation_threshold: u64,
+    ) -> Result<()> {
+        bonding_curve::configure_bonding_curve(
+            ctx,
+            curve_type,
+            curve_base_price,
+            curve_step_size,
+            curve_step_increment,
+            curve_graduation_threshold,
+        )
+    }
+
+    pub fn buy_from_curve(ctx: Context<CurveTrade>, amount: u64, max_sol_cost: u64) -> Result<()> {
+        bonding_curve::buy_from_curve(ctx, amount, max_sol_cost)
+    }
+
+    pub fn sell_to_curve(ctx: Context<CurveTrade>, amount: u64, min_sol_out: u64) -> Result<()> {
+        bonding_curve::sell_to_curve(ctx, amount, min_sol_out)
+    }
+
     // Staking functions
 
-    pub fn stake(ctx: Context<StakeTokens>, amount: u64) -> Result<()> {
-        staking::stake_tokens(ctx, amount)
+    pub fn stake(
+        ctx: Context<StakeTokens>,
+        position_index: u32,
+        amount: u64,
+        lock_term: u8,
+    ) -> Result<()> {
+        staking::stake_tokens(ctx, position_index, amount, lock_term)
+    }
+
+    pub fn stake_for(
+        ctx: Context<StakeFor>,
+        position_index: u32,
+        amount: u64,
+        lock_term: u8,
+    ) -> Result<()> {
+        staking::stake_for(ctx, position_index, amount, lock_term)
+    }
+
+    pub fn set_stake_delegate(
+        ctx: Context<SetStakeDelegate>,
+        delegate: Option<Pubkey>,
+    ) -> Result<()> {
+        staking::set_stake_delegate(ctx, delegate)
     }
 
     pub fn unstake(ctx: Context<UnstakeTokens>, amount: u64) -> Result<()> {
         staking::unstake_tokens(ctx, amount)
     }
 
+    pub fn close_user_stake(ctx: Context<CloseUserStake>) -> Result<()> {
+        staking::close_user_stake(ctx)
+    }
+
+    pub fn refresh_stake_summary(ctx: Context<RefreshStakeSummary>) -> Result<()> {
+        staking::refresh_stake_summary(ctx)
+    }
+
+    pub fn add_staking_mint(ctx: Context<AddStakingMint>, weight_bps: u16) -> Result<()> {
+        staking::add_staking_mint(ctx, weight_bps)
+    }
+
+    pub fn update_lp_rate(ctx: Context<UpdateLpRate>) -> Result<()> {
+        staking::update_lp_rate(ctx)
+    }
+
+    pub fn update_staking_mint(
+        ctx: Context<UpdateStakingMint>,
+        weight_bps: u16,
+        enabled: bool,
+    ) -> Result<()> {
+        staking::update_staking_mint(ctx, weight_bps, enabled)
+    }
+
+    // Staking reward functions
+
+    pub fn initialize_staking_pool(
+        ctx: Context<InitializeStakingPool>,
+        reward_rate: u64,
+    ) -> Result<()> {
+        rewards::initialize_staking_pool(ctx, reward_rate)
+    }
+
+    pub fn fund_reward_vault(ctx: Context<FundRewardVault>, amount: u64) -> Result<()> {
+        rewards::fund_reward_vault(ctx, amount)
+    }
+
+    pub fn set_reward_rate(ctx: Context<SetRewardRate>, reward_rate: u64) -> Result<()> {
+        rewards::set_reward_rate(ctx, reward_rate)
+    }
+
+    pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
+        rewards::claim_staking_rewards(ctx)
+    }
+
+    pub fn claim_staker_revenue(ctx: Context<ClaimStakerRevenue>) -> Result<()> {
+        rewards::claim_staker_revenue(ctx)
+    }
+
     // Vesting/claiming functions
 
     pub fn claim_tokens(ctx: Context<ClaimTokens>) -> Result<()> {
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::*;
+use crate::state::*;
+
+/// Validates and writes the human-readable presale details.
+pub fn set_presale_metadata(
+    metadata: &mut PresaleMetadata,
+    args: PresaleMetadataArgs,
+) -> Result<()> {
+    require!(
+        args.name.len() <= METADATA_MAX_NAME_LEN
+            && args.symbol.len() <= METADATA_MAX_SYMBOL_LEN
+            && args.uri.len() <= METADATA_MAX_URI_LEN,
+        IdoError::MetadataTooLong
+    );
+
+    metadata.name = args.name;
+    metadata.symbol = args.symbol;
+    metadata.uri = args.uri;
+    metadata.content_hash = args.content_hash;
+
+    Ok(())
+}
+
+/// Lets the creator correct the metadata until the presale is approved.
+pub fn update_presale_metadata(
+    ctx: Context<UpdatePresaleMetadata>,
+    metadata: PresaleMetadataArgs,
+) -> Result<()> {
+    set_presale_metadata(&mut ctx.accounts.presale_metadata, metadata)?;
+
+    msg!("Presale metadata updated");
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdatePresaleMetadata<'info> {
+    #[account(
+        constraint = creator.key() == presale.creator @ IdoError::Unauthorized
+    )]
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_PENDING @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE_METADATA,
+            presale.key().as_ref()
+        ],
+        bump = presale_metadata.bump
+    )]
+    pub presale_metadata: Account<'info, PresaleMetadata>,
+}
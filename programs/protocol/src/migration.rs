@@ -0,0 +1,392 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::rewards::*;
+use crate::staking::*;
+use crate::state::*;
+use crate::utils::*;
+
+/// Account types that carry a layout version and can be upgraded in place.
+/// Fields are only ever appended after the original layout, so a reallocated
+/// older account decodes with the new fields zeroed and `version` 0.
+pub trait Versioned: AccountSerialize + AccountDeserialize {
+    /// Layout version written by the current program.
+    const CURRENT_VERSION: u8;
+
+    /// Size of the current layout, discriminator included.
+    const SPACE: usize;
+
+    fn version(&self) -> u8;
+
+    fn set_version(&mut self, version: u8);
+
+    /// Wallet that may migrate the account besides the admin.
+    fn migration_owner(&self) -> Pubkey;
+
+    /// Fills in fields introduced by the layout after `from_version` whose
+    /// default isn't zero.
+    fn upgrade_from(&mut self, _from_version: u8) {}
+}
+
+impl Versioned for GlobalState {
+    const CURRENT_VERSION: u8 = ACCOUNT_VERSION;
+    const SPACE: usize = 8 + GlobalState::INIT_SPACE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migration_owner(&self) -> Pubkey {
+        self.admin
+    }
+}
+
+impl Versioned for Presale {
+    const CURRENT_VERSION: u8 = ACCOUNT_VERSION;
+    const SPACE: usize = 8 + Presale::INIT_SPACE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migration_owner(&self) -> Pubkey {
+        self.creator
+    }
+
+    fn upgrade_from(&mut self, from_version: u8) {
+        if from_version == 0 {
+            // Rounds before per-round vesting used the fixed schedule
+            self.vesting_percentages = [
+                VESTING_FIRST_RELEASE_PERCENTAGE,
+                VESTING_SECOND_RELEASE_PERCENTAGE,
+                VESTING_THIRD_RELEASE_PERCENTAGE,
+            ];
+            self.legacy_seeds = true;
+        }
+
+        // Version 1 ended before the liquidity reserve. Rounds without a
+        // reserve list with the raise alone, so zero needs no default.
+    }
+}
+
+impl Versioned for UserStake {
+    const CURRENT_VERSION: u8 = ACCOUNT_VERSION;
+    const SPACE: usize = 8 + UserStake::INIT_SPACE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migration_owner(&self) -> Pubkey {
+        self.user
+    }
+
+    fn upgrade_from(&mut self, from_version: u8) {
+        if from_version == 0 {
+            // Single-stake positions held the primary mint with no lock
+            self.lock_weight_bps = LOCK_TERM_FLEXIBLE_WEIGHT_BPS;
+            self.mint_weight_bps = BPS_DENOMINATOR as u16;
+            self.power_amount = self.amount;
+            self.rent_payer = self.user;
+            self.legacy_seeds = true;
+        }
+    }
+}
+
+impl Versioned for UserPresaleInfo {
+    const CURRENT_VERSION: u8 = ACCOUNT_VERSION;
+    const SPACE: usize = 8 + UserPresaleInfo::INIT_SPACE;
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migration_owner(&self) -> Pubkey {
+        self.user
+    }
+
+    fn upgrade_from(&mut self, from_version: u8) {
+        if from_version == 0 {
+            // The user paid for the account under the original program
+            self.rent_payer = self.user;
+        }
+    }
+}
+
+/// Grows a program account to `space` bytes, topping up rent from `payer`.
+/// Fields appended since the account was created read back as zero.
+fn realloc_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    require!(
+        account.owner == &crate::ID,
+        IdoError::InvalidMigrationAccount
+    );
+
+    if account.data_len() >= space {
+        return Ok(());
+    }
+
+    let required_lamports = Rent::get()?.minimum_balance(space);
+    let top_up = required_lamports.saturating_sub(account.lamports());
+
+    if top_up > 0 {
+        let cpi_accounts = Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        system_program::transfer(
+            CpiContext::new(system_program.clone(), cpi_accounts),
+            top_up,
+        )?;
+    }
+
+    account.realloc(space, true)?;
+
+    Ok(())
+}
+
+/// Reads an account already sized for the current layout, walks it through
+/// every upgrade step since its stored version and writes it back. The
+/// discriminator check rejects accounts of another type.
+pub fn upgrade_account_data<T: Versioned>(data: &mut [u8]) -> Result<T> {
+    require!(data.len() >= T::SPACE, IdoError::InvalidMigrationAccount);
+
+    let mut state = T::try_deserialize(&mut &data[..])?;
+
+    require!(
+        state.version() < T::CURRENT_VERSION,
+        IdoError::AccountAlreadyMigrated
+    );
+
+    for from_version in state.version()..T::CURRENT_VERSION {
+        state.upgrade_from(from_version);
+    }
+    state.set_version(T::CURRENT_VERSION);
+
+    state.try_serialize(&mut &mut data[..])?;
+
+    Ok(state)
+}
+
+/// Reallocs and upgrades `account`. The signer pays any extra rent and must
+/// be the admin or the account's owner. Returns the upgraded state and the
+/// version it was stored with.
+fn migrate_account<'info, T: Versioned>(
+    account: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    admin: Pubkey,
+    system_program: &AccountInfo<'info>,
+) -> Result<(T, u8)> {
+    realloc_account(account, authority, system_program, T::SPACE)?;
+
+    let mut data = account.try_borrow_mut_data()?;
+    let stored_version = T::try_deserialize(&mut &data[..])?.version();
+    let state = upgrade_account_data::<T>(&mut data)?;
+
+    require!(
+        authority.key() == admin || authority.key() == state.migration_owner(),
+        IdoError::Unauthorized
+    );
+
+    Ok((state, stored_version))
+}
+
+pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> Result<()> {
+    let global_state = ctx.accounts.global_state.to_account_info();
+
+    // The admin is the first field, so it can be read before the migration
+    let admin = {
+        let data = global_state.try_borrow_data()?;
+        require!(data.len() >= 8 + 32, IdoError::InvalidMigrationAccount);
+        Pubkey::try_from(&data[8..8 + 32]).unwrap()
+    };
+
+    migrate_account::<GlobalState>(
+        &global_state,
+        &ctx.accounts.authority.to_account_info(),
+        admin,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    msg!("Global state migrated to version {}", GlobalState::CURRENT_VERSION);
+
+    Ok(())
+}
+
+pub fn migrate_presale(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate_account::<Presale>(
+        &ctx.accounts.account.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        ctx.accounts.global_state.admin,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    msg!("Presale migrated to version {}", Presale::CURRENT_VERSION);
+
+    Ok(())
+}
+
+pub fn migrate_user_stake(ctx: Context<MigrateUserStake>) -> Result<()> {
+    let (mut user_stake, stored_version) = migrate_account::<UserStake>(
+        &ctx.accounts.account.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        ctx.accounts.global_state.admin,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    // Single-stake accounts were never counted in a summary or staking pool
+    if stored_version == 0 {
+        require!(
+            ctx.accounts.owner.key() == user_stake.user
+                && ctx.accounts.staking_pool.staking_token_mint == user_stake.staking_token_mint,
+            IdoError::InvalidMigrationAccount
+        );
+
+        let summary = &mut ctx.accounts.user_stake_summary;
+        let staking_pool = &mut ctx.accounts.staking_pool;
+        let current_time = Clock::get()?.unix_timestamp;
+        let (previous_total, previous_tier) = (summary.total_power, summary.tier);
+
+        if summary.user == Pubkey::default() {
+            summary.user = user_stake.user;
+            summary.boost_expiry = i64::MAX;
+            summary.bump = ctx.bumps.user_stake_summary;
+        }
+        summary.position_count = summary.position_count.checked_add(1).unwrap();
+
+        // Joins the pool from now on, without rewards accrued before migration
+        update_pool_rewards(staking_pool, current_time);
+        staking_pool.total_staked = staking_pool.total_staked.checked_add(user_stake.amount).unwrap();
+        reset_reward_debt(staking_pool, &mut user_stake);
+
+        apply_position_to_summary(summary, &mut user_stake, 0, current_time);
+        record_stake_change(&mut ctx.accounts.global_state, previous_total, previous_tier, summary);
+
+        let account = ctx.accounts.account.to_account_info();
+        let mut data = account.try_borrow_mut_data()?;
+        user_stake.try_serialize(&mut &mut data[..])?;
+    }
+
+    msg!("User stake migrated to version {}", UserStake::CURRENT_VERSION);
+
+    Ok(())
+}
+
+pub fn migrate_user_presale_info(ctx: Context<MigrateAccount>) -> Result<()> {
+    migrate_account::<UserPresaleInfo>(
+        &ctx.accounts.account.to_account_info(),
+        &ctx.accounts.authority.to_account_info(),
+        ctx.accounts.global_state.admin,
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    msg!("User presale info migrated to version {}", UserPresaleInfo::CURRENT_VERSION);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateGlobalState<'info> {
+    // Checked against the admin stored in the account being migrated
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump
+    )]
+    /// CHECK: May still use the old layout; the admin is read from raw data
+    pub global_state: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Global state must be migrated first
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    // The admin or the owner of the account being migrated
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    /// CHECK: Owner and discriminator are checked during the migration
+    pub account: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Global state must be migrated first. Stakes from the single-stake layout
+// are added to the owner's summary and their mint's staking pool.
+#[derive(Accounts)]
+pub struct MigrateUserStake<'info> {
+    // The admin or the owner of the stake; pays for the summary if needed
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    /// CHECK: Owner and discriminator are checked during the migration
+    pub account: UncheckedAccount<'info>,
+
+    /// CHECK: Checked against the wallet stored in the stake
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + UserStakeSummary::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_STAKE_SUMMARY,
+            owner.key().as_ref()
+        ],
+        bump
+    )]
+    pub user_stake_summary: Account<'info, UserStakeSummary>,
+
+    // Pool of the stake's mint, checked during the migration
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_pool.staking_token_mint.as_ref()
+        ],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,211 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{program::invoke, system_instruction};
+use crate::constants::*;
+use crate::errors::*;
+use crate::presale::ConfigurePresale;
+use crate::state::*;
+use crate::utils::*;
+
+pub fn configure_overflow_sale(ctx: Context<ConfigurePresale>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+
+    require!(
+        presale.sale_mode == SALE_MODE_FIXED_PRICE,
+        IdoError::InvalidSaleMode
+    );
+
+    // Overflow sales never leave tokens unsold for an FCFS round
+    require!(!presale.fcfs_enabled, IdoError::InvalidSaleMode);
+
+    presale.sale_mode = SALE_MODE_OVERFLOW;
+
+    msg!("Presale switched to overflow mode");
+
+    Ok(())
+}
+
+pub fn commit_sol(ctx: Context<CommitSol>, amount: u64) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+
+    let presale = &mut ctx.accounts.presale;
+    let user_info = &mut ctx.accounts.user_info;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.sale_mode == SALE_MODE_OVERFLOW,
+        IdoError::InvalidSaleMode
+    );
+
+    // Ensure presale is within time bounds
+    require!(
+        current_time >= presale.start_time,
+        IdoError::PresaleNotStarted
+    );
+
+    require!(current_time <= presale.end_time, IdoError::PresaleEnded);
+
+    require!(amount > 0, IdoError::InsufficientAllocation);
+
+    let presale_key = presale.key();
+    let presale_info = presale.to_account_info();
+
+    // Transfer SOL from user to presale account
+    invoke(
+        &system_instruction::transfer(&user_key, &presale_key, amount),
+        &[
+            ctx.accounts.user.to_account_info(),
+            presale_info,
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    presale.total_committed = presale.total_committed.checked_add(amount).unwrap();
+    if user_info.sol_committed == 0 {
+        presale.open_settlements = presale.open_settlements.checked_add(1).unwrap();
+    }
+    user_info.sol_committed = user_info.sol_committed.checked_add(amount).unwrap();
+
+    msg!("User committed {} lamports to the presale", amount);
+
+    Ok(())
+}
+
+/// Returns the number of tokens a commitment is worth in an overflow sale:
+/// `tokens_for_sale * sol_committed / total_committed`, capped at what the
+/// commitment buys at `token_price`. The cap only bites when the sale is
+/// undersubscribed, where a plain pro-rata split would hand out the whole
+/// supply below the sale price; the unsold tokens stay with the presale
+/// instead.
+pub fn calculate_overflow_allocation(sol_committed: u64, presale: &Presale) -> u64 {
+    if presale.total_committed == 0 || presale.token_price == 0 {
+        return 0;
+    }
+
+    let pro_rata = (presale.tokens_for_sale as u128)
+        .checked_mul(sol_committed as u128)
+        .unwrap()
+        .checked_div(presale.total_committed as u128)
+        .unwrap() as u64;
+
+    let at_price = sol_committed.checked_div(presale.token_price).unwrap();
+
+    std::cmp::min(pro_rata, at_price)
+}
+
+pub fn settle_overflow(ctx: Context<SettleOverflow>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let user_info = &mut ctx.accounts.user_info;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.sale_mode == SALE_MODE_OVERFLOW,
+        IdoError::InvalidSaleMode
+    );
+
+    // Settlement only happens once the commitment window is closed
+    require!(current_time > presale.end_time, IdoError::PresaleNotCompleted);
+
+    require!(!user_info.settled, IdoError::AlreadySettled);
+
+    require!(user_info.sol_committed > 0, IdoError::NothingToSettle);
+
+    let tokens = calculate_overflow_allocation(user_info.sol_committed, presale);
+    let cost = tokens.checked_mul(presale.token_price).unwrap();
+    let refund = user_info.sol_committed.checked_sub(cost).unwrap();
+
+    // Return the excess commitment to the user
+    if refund > 0 {
+        transfer_lamports_from_pda(
+            &presale.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            refund,
+        )?;
+    }
+
+    // Update presale info
+    presale.tokens_sold = presale.tokens_sold.checked_add(tokens).unwrap();
+    presale.sol_raised = presale.sol_raised.checked_add(cost).unwrap();
+
+    // Settled tokens are released through claim_tokens
+    user_info.allocation = user_info.allocation.checked_add(tokens).unwrap();
+    user_info.purchased = user_info.purchased.checked_add(tokens).unwrap();
+    user_info.settled = true;
+    presale.open_settlements = presale.open_settlements.saturating_sub(1);
+
+    msg!(
+        "User settled {} tokens and was refunded {} lamports",
+        tokens,
+        refund
+    );
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CommitSol<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_LIVE @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_INFO,
+            user.key().as_ref(),
+            presale.key().as_ref(),
+        ],
+        bump = user_info.bump,
+        constraint = user_info.user == user.key(),
+        constraint = user_info.presale == presale.key()
+    )]
+    pub user_info: Account<'info, UserPresaleInfo>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleOverflow<'info> {
+    /// CHECK: Receives the refund and is tied to the user info by its seeds.
+    /// Settling is permissionless so the protocol fee isn't held up by
+    /// users who never settle.
+    #[account(mut)]
+    pub user: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_LIVE || presale.status == STATUS_COMPLETED @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_INFO,
+            user.key().as_ref(),
+            presale.key().as_ref(),
+        ],
+        bump = user_info.bump,
+        constraint = user_info.user == user.key(),
+        constraint = user_info.presale == presale.key()
+    )]
+    pub user_info: Account<'info, UserPresaleInfo>,
+}
@@ -0,0 +1,274 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::presale::ManagePresale;
+use crate::rewards::*;
+use crate::staking::*;
+use crate::state::*;
+use crate::tier::*;
+use crate::utils::*;
+
+pub fn configure_no_show_penalty(
+    ctx: Context<ManagePresale>,
+    no_show_cooldown: i64,
+    no_show_slash_bps: u16,
+) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // Terms must be known before registration can lead to a purchase. The
+    // start time is checked too since start_presale may run late.
+    require!(
+        (presale.status == STATUS_PENDING || presale.status == STATUS_APPROVED)
+            && current_time < presale.start_time,
+        IdoError::InvalidPresaleStatus
+    );
+
+    // Only tier and allowlist rounds hand out allocations to leave unused
+    require!(
+        presale.sale_mode == SALE_MODE_FIXED_PRICE
+            || presale.sale_mode == SALE_MODE_DUTCH_AUCTION,
+        IdoError::InvalidSaleMode
+    );
+
+    require!(no_show_cooldown >= 0, IdoError::InvalidTimeSetup);
+
+    require!(
+        no_show_slash_bps as u64 <= BPS_DENOMINATOR,
+        IdoError::InvalidBasisPoints
+    );
+
+    presale.no_show_cooldown = no_show_cooldown;
+    presale.no_show_slash_bps = no_show_slash_bps;
+
+    msg!(
+        "No-show penalty set: {} seconds cooldown, {} bps slash",
+        no_show_cooldown,
+        no_show_slash_bps
+    );
+
+    Ok(())
+}
+
+/// Whether the registrant left a usable allocation untouched and is still
+/// owed the penalty. Registrations for which this holds can't be closed.
+pub fn is_penalizable_no_show(presale: &Presale, user_info: &UserPresaleInfo) -> bool {
+    let penalty_enabled = presale.no_show_cooldown > 0 || presale.no_show_slash_bps > 0;
+
+    // Only tier and allowlist rounds hand out allocations to leave unused
+    let allocated_mode = presale.sale_mode == SALE_MODE_FIXED_PRICE
+        || presale.sale_mode == SALE_MODE_DUTCH_AUCTION;
+
+    let is_no_show = !user_info.penalized
+        && user_info.purchased == 0
+        && user_info.sol_committed == 0;
+
+    // A sold-out pool is no fault of the registrant
+    let could_have_bought = user_info.allocation > 0
+        || has_unsold_tier_allocation(user_info.registered_tier, presale);
+
+    penalty_enabled && allocated_mode && is_no_show && could_have_bought
+}
+
+/// Permissionless crank once the sale is completed. Registrants who left a
+/// usable allocation untouched lose tier eligibility for the configured
+/// cooldown and, if configured, forfeit part of every stake position to the
+/// treasury. When slashing, `remaining_accounts` holds each open position
+/// of the user as (user stake, stake token account, staking pool, treasury
+/// token account), all writable. Allowlisted users who never staked have no
+/// stake summary and are only marked as penalized.
+pub fn penalize_no_show<'info>(ctx: Context<'_, '_, 'info, 'info, PenalizeNoShow<'info>>) -> Result<()> {
+    let presale = &ctx.accounts.presale;
+    let user_info = &mut ctx.accounts.user_info;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.no_show_cooldown > 0 || presale.no_show_slash_bps > 0,
+        IdoError::NoShowPenaltyDisabled
+    );
+
+    require!(
+        is_penalizable_no_show(presale, user_info),
+        IdoError::NotANoShow
+    );
+
+    user_info.penalized = true;
+
+    let summary_info = ctx.accounts.user_stake_summary.to_account_info();
+    if summary_info.owner != &crate::ID {
+        require!(ctx.remaining_accounts.is_empty(), IdoError::InvalidSlashAccounts);
+        msg!("No-show penalty applied");
+        return Ok(());
+    }
+
+    let mut stake_summary =
+        UserStakeSummary::try_deserialize(&mut &summary_info.try_borrow_data()?[..])?;
+    let summary = &mut stake_summary;
+    let (previous_total, previous_tier) = (summary.total_power, summary.tier);
+
+    if presale.no_show_cooldown > 0 {
+        let cooldown_end = current_time.checked_add(presale.no_show_cooldown).unwrap();
+        summary.tier_cooldown_until = summary.tier_cooldown_until.max(cooldown_end);
+    }
+
+    if presale.no_show_slash_bps > 0 {
+        require!(
+            ctx.remaining_accounts.len() as u64 == summary.position_count as u64 * 4,
+            IdoError::InvalidSlashAccounts
+        );
+
+        let mut position_keys = Vec::with_capacity(summary.position_count as usize);
+        let mut total_slashed: u64 = 0;
+
+        for accounts in ctx.remaining_accounts.chunks(4) {
+            // Loaded one position at a time so a pool shared by several
+            // positions sees the previous update
+            let mut user_stake = Account::<UserStake>::try_from(&accounts[0])?;
+            let stake_token_account = Account::<TokenAccount>::try_from(&accounts[1])?;
+            let mut staking_pool = Account::<StakingPool>::try_from(&accounts[2])?;
+            let treasury_token_account = Account::<TokenAccount>::try_from(&accounts[3])?;
+
+            require!(
+                user_stake.user == user_info.user
+                    && stake_token_account.owner == user_stake.key()
+                    && stake_token_account.mint == user_stake.staking_token_mint
+                    && treasury_token_account.owner == ctx.accounts.global_state.treasury_wallet
+                    && treasury_token_account.mint == user_stake.staking_token_mint
+                    && staking_pool.staking_token_mint == user_stake.staking_token_mint,
+                IdoError::InvalidSlashAccounts
+            );
+
+            position_keys.push(user_stake.key());
+
+            let slash_amount = user_stake
+                .amount
+                .checked_mul(presale.no_show_slash_bps as u64)
+                .unwrap()
+                .checked_div(BPS_DENOMINATOR)
+                .unwrap();
+
+            if slash_amount == 0 {
+                continue;
+            }
+
+            // Settle rewards earned on the existing stake before it shrinks
+            update_pool_rewards(&mut staking_pool, current_time);
+            settle_user_rewards(&staking_pool, &mut user_stake);
+
+            let position_index = user_stake.index_seed();
+            let seeds = &[
+                SEED_PREFIX_USER_STAKE,
+                user_stake.user.as_ref(),
+                user_stake.staking_token_mint.as_ref(),
+                position_index.as_ref(),
+                &[user_stake.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: stake_token_account.to_account_info(),
+                to: treasury_token_account.to_account_info(),
+                authority: user_stake.to_account_info(),
+            };
+
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            token::transfer(
+                CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+                slash_amount,
+            )?;
+
+            // Scale the power snapshot so LP positions don't need the reserve
+            let previous_power = get_weighted_power(&user_stake);
+            let remaining = user_stake.amount.checked_sub(slash_amount).unwrap();
+            user_stake.power_amount = ((user_stake.power_amount as u128)
+                .checked_mul(remaining as u128)
+                .unwrap()
+                .checked_div(user_stake.amount as u128)
+                .unwrap()) as u64;
+            user_stake.amount = remaining;
+
+            apply_position_to_summary(summary, &mut user_stake, previous_power, current_time);
+
+            staking_pool.total_staked = staking_pool.total_staked.saturating_sub(slash_amount);
+            reset_reward_debt(&staking_pool, &mut user_stake);
+
+            user_stake.exit(&crate::ID)?;
+            staking_pool.exit(&crate::ID)?;
+
+            total_slashed = total_slashed.checked_add(slash_amount).unwrap();
+        }
+
+        // Reject duplicates so every open position is slashed exactly once
+        position_keys.sort();
+        position_keys.dedup();
+        require!(
+            position_keys.len() as u64 == summary.position_count as u64,
+            IdoError::InvalidSlashAccounts
+        );
+
+        record_stake_change(
+            &mut ctx.accounts.global_state,
+            previous_total,
+            previous_tier,
+            summary,
+        );
+
+        msg!("No-show slashed {} staked tokens", total_slashed);
+    }
+
+    summary.try_serialize(&mut &mut summary_info.try_borrow_mut_data()?[..])?;
+
+    msg!("No-show penalty applied");
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PenalizeNoShow<'info> {
+    #[account(
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_COMPLETED @ IdoError::PresaleNotCompleted
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_INFO,
+            user_info.user.as_ref(),
+            presale.key().as_ref(),
+        ],
+        bump = user_info.bump,
+        constraint = user_info.presale == presale.key()
+    )]
+    pub user_info: Account<'info, UserPresaleInfo>,
+
+    /// CHECK: Tied to the user by its seeds. Left uninitialized for
+    /// allowlisted users who never staked; read and written in the handler
+    /// otherwise.
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKE_SUMMARY,
+            user_info.user.as_ref()
+        ],
+        bump
+    )]
+    pub user_stake_summary: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub token_program: Program<'info, Token>,
+}
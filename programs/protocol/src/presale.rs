@@ -1,26 +1,26 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{program::invoke, system_instruction};
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer};
 use crate::constants::*;
+use crate::dutch_auction::*;
 use crate::errors::*;
+use crate::events::*;
+use crate::metadata::*;
+use crate::penalty::is_penalizable_no_show;
+use crate::project::*;
+use crate::rewards::*;
 use crate::state::*;
 use crate::tier::*;
+use crate::utils::*;
 
-pub fn create_presale(
-    ctx: Context<CreatePresale>,
-    tokens_for_sale: u64,
-    token_price: u64,
-    start_time: i64,
-    end_time: i64,
+/// Registration must open in the future and run strictly before the sale.
+fn validate_presale_times(
     registration_start_time: i64,
     registration_end_time: i64,
-    listing_price: u64,
-    vesting_enabled: bool,
+    start_time: i64,
+    end_time: i64,
+    current_time: i64,
 ) -> Result<()> {
-    let presale = &mut ctx.accounts.presale;
-    let current_time = Clock::get()?.unix_timestamp;
-
-    // Validate time setup
     require!(
         registration_start_time < registration_end_time,
         IdoError::InvalidTimeSetup
@@ -38,9 +38,193 @@ pub fn create_presale(
         IdoError::InvalidTimeSetup
     );
 
+    Ok(())
+}
+
+/// The pool must open inside the configured band around the sale price, so
+/// buyers aren't listed below what they paid unless the admin allows it.
+pub fn validate_listing_price(
+    token_price: u64,
+    listing_price: u64,
+    global_state: &GlobalState,
+) -> Result<()> {
+    let band_price = |bps: u16| {
+        (token_price as u128)
+            .checked_mul(bps as u128)
+            .unwrap()
+            .checked_div(BPS_DENOMINATOR as u128)
+            .unwrap()
+    };
+
+    let min_price = if global_state.listing_band_min_bps == 0 {
+        token_price as u128
+    } else {
+        band_price(global_state.listing_band_min_bps)
+    };
+
+    require!(
+        listing_price > 0 && listing_price as u128 >= min_price,
+        IdoError::InvalidListingPrice
+    );
+
+    if global_state.listing_band_max_bps > 0 {
+        require!(
+            listing_price as u128 <= band_price(global_state.listing_band_max_bps),
+            IdoError::InvalidListingPrice
+        );
+    }
+
+    Ok(())
+}
+
+/// The liquidity reserve must pair the listed share of a sold-out raise at
+/// the listing price, so list_token never dips into buyers' tokens.
+pub fn validate_liquidity_reserve(
+    tokens_for_sale: u64,
+    token_price: u64,
+    tokens_for_liquidity: u64,
+    listing_price: u64,
+) -> Result<()> {
+    let sol_to_liquidity = (tokens_for_sale as u128)
+        .checked_mul(token_price as u128)
+        .unwrap()
+        .checked_mul(LISTING_LIQUIDITY_PERCENTAGE as u128)
+        .unwrap()
+        .checked_div(100)
+        .unwrap();
+
+    require!(
+        (tokens_for_liquidity as u128)
+            .checked_mul(listing_price as u128)
+            .unwrap()
+            >= sol_to_liquidity,
+        IdoError::InsufficientLiquidityReserve
+    );
+
+    Ok(())
+}
+
+/// Re-checks the listing band and liquidity reserve of a presale whose sale
+/// mode can charge up to `highest_price` per token.
+pub fn validate_listing_terms(
+    presale: &Presale,
+    highest_price: u64,
+    global_state: &GlobalState,
+) -> Result<()> {
+    validate_listing_price(highest_price, presale.listing_price, global_state)?;
+
+    validate_liquidity_reserve(
+        presale.tokens_for_sale,
+        highest_price,
+        presale.tokens_for_liquidity,
+        presale.listing_price,
+    )
+}
+
+/// SOL and tokens paired into the pool at listing. Presales created before
+/// the liquidity reserve pair the listed share of the raise uncapped, as
+/// they always did.
+pub fn calculate_listing_amounts(presale: &Presale) -> (u64, u64) {
+    // Calculate the amount of SOL to add to liquidity (80% of SOL raised)
+    let mut sol_to_liquidity = presale
+        .sol_raised
+        .checked_mul(LISTING_LIQUIDITY_PERCENTAGE as u64)
+        .unwrap()
+        .checked_div(100)
+        .unwrap();
+
+    // Pair it with enough tokens for the pool to open at the listing price
+    let mut tokens_to_liquidity = sol_to_liquidity
+        .checked_div(presale.listing_price)
+        .unwrap();
+
+    // Auctions can raise more than the reserve pairs; the pool still opens
+    // at the listing price and the rest of the SOL stays with the presale
+    if presale.tokens_for_liquidity > 0 && tokens_to_liquidity > presale.tokens_for_liquidity {
+        tokens_to_liquidity = presale.tokens_for_liquidity;
+        sol_to_liquidity = tokens_to_liquidity.checked_mul(presale.listing_price).unwrap();
+    }
+
+    (sol_to_liquidity, tokens_to_liquidity)
+}
+
+/// Pays the configured application fee to the treasury, in SOL or in the
+/// staking token depending on the global config.
+fn charge_application_fee(ctx: &Context<CreatePresale>) -> Result<()> {
+    let global_state = &ctx.accounts.global_state;
+    let fee = global_state.application_fee;
+
+    if fee == 0 {
+        return Ok(());
+    }
+
+    if global_state.application_fee_in_staking_token {
+        let (Some(creator_fee_token_account), Some(treasury_fee_token_account)) = (
+            ctx.accounts.creator_fee_token_account.as_ref(),
+            ctx.accounts.treasury_fee_token_account.as_ref(),
+        ) else {
+            return err!(IdoError::InvalidFeeAccounts);
+        };
+
+        let cpi_accounts = Transfer {
+            from: creator_fee_token_account.to_account_info(),
+            to: treasury_fee_token_account.to_account_info(),
+            authority: ctx.accounts.creator.to_account_info(),
+        };
+
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            fee,
+        )?;
+    } else {
+        let Some(treasury_wallet) = ctx.accounts.treasury_wallet.as_ref() else {
+            return err!(IdoError::InvalidFeeAccounts);
+        };
+
+        invoke(
+            &system_instruction::transfer(&ctx.accounts.creator.key(), &treasury_wallet.key(), fee),
+            &[
+                ctx.accounts.creator.to_account_info(),
+                treasury_wallet.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+    }
+
+    msg!("Application fee of {} paid to the treasury", fee);
+
+    Ok(())
+}
+
+pub fn create_presale(
+    ctx: Context<CreatePresale>,
+    params: CreatePresaleParams,
+    metadata: PresaleMetadataArgs,
+) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // Validate time setup
+    validate_presale_times(
+        params.registration_start_time,
+        params.registration_end_time,
+        params.start_time,
+        params.end_time,
+        current_time,
+    )?;
+
+    validate_listing_price(params.token_price, params.listing_price, &ctx.accounts.global_state)?;
+
+    validate_liquidity_reserve(
+        params.tokens_for_sale,
+        params.token_price,
+        params.tokens_for_liquidity,
+        params.listing_price,
+    )?;
+
     // Calculate tier allocations
     let (tier1_allocation, tier2_allocation, tier3_allocation) =
-        calculate_presale_tier_allocations(tokens_for_sale);
+        calculate_presale_tier_allocations(params.tokens_for_sale);
 
     // Initialize presale
 
@@ -48,13 +232,14 @@ pub fn create_presale(
     presale.creator = ctx.accounts.creator.key();
     presale.mint_of_token_being_sold = ctx.accounts.mint_of_token_being_sold.key();
     presale.status = STATUS_PENDING; // Starts as pending until admin approves
-    presale.token_price = token_price;
-    presale.tokens_for_sale = tokens_for_sale;
+    presale.token_price = params.token_price;
+    presale.tokens_for_sale = params.tokens_for_sale;
+    presale.tokens_for_liquidity = params.tokens_for_liquidity;
     presale.tokens_sold = 0;
-    presale.start_time = start_time;
-    presale.end_time = end_time;
-    presale.registration_start_time = registration_start_time;
-    presale.registration_end_time = registration_end_time;
+    presale.start_time = params.start_time;
+    presale.end_time = params.end_time;
+    presale.registration_start_time = params.registration_start_time;
+    presale.registration_end_time = params.registration_end_time;
     presale.tier1_allocation = tier1_allocation;
     presale.tier2_allocation = tier2_allocation;
     presale.tier3_allocation = tier3_allocation;
@@ -62,46 +247,287 @@ pub fn create_presale(
     presale.tier2_sold = 0;
     presale.tier3_sold = 0;
     presale.sol_raised = 0;
-    presale.listing_price = listing_price;
+    presale.listing_price = params.listing_price;
     presale.is_listed = false;
     presale.presale_token_account = ctx.accounts.presale_token_account.key();
     presale.presale_sol_account = ctx.accounts.global_state.treasury_wallet;
-    presale.vesting_enabled = vesting_enabled;
+    presale.vesting_enabled = params.vesting_enabled;
 
     // Set vesting times if enabled
-    if vesting_enabled {
-        presale.first_release_time = end_time;
-        presale.second_release_time = end_time + VESTING_FIRST_RELEASE_DURATION;
-        presale.third_release_time = end_time + VESTING_SECOND_RELEASE_DURATION;
+    if params.vesting_enabled {
+        presale.first_release_time = params.end_time;
+        presale.second_release_time = params.end_time + VESTING_FIRST_RELEASE_DURATION;
+        presale.third_release_time = params.end_time + VESTING_SECOND_RELEASE_DURATION;
     } else {
-        presale.first_release_time = end_time;
-        presale.second_release_time = end_time;
-        presale.third_release_time = end_time;
+        presale.first_release_time = params.end_time;
+        presale.second_release_time = params.end_time;
+        presale.third_release_time = params.end_time;
     }
 
+    // FCFS round is opt-in via configure_fcfs
+    presale.fcfs_enabled = false;
+    presale.fcfs_start_time = 0;
+    presale.fcfs_end_time = 0;
+    presale.fcfs_wallet_cap = 0;
+
+    // Fixed price unless switched to another mode while pending
+    presale.sale_mode = SALE_MODE_FIXED_PRICE;
+    presale.total_committed = 0;
+    presale.auction_start_price = 0;
+    presale.auction_floor_price = 0;
+    presale.auction_last_price = 0;
+    presale.bid_count = 0;
+    presale.batch_cleared = false;
+    presale.clearing_price = 0;
+    presale.batch_supply_at_clearing = 0;
+    presale.batch_demand_at_clearing = 0;
+    presale.batch_failed = false;
+    presale.curve_type = CURVE_TYPE_LINEAR;
+    presale.curve_base_price = 0;
+    presale.curve_step_size = 0;
+    presale.curve_step_increment = 0;
+    presale.curve_graduation_threshold = 0;
+    presale.protocol_fee_collected = false;
+    presale.no_show_cooldown = 0;
+    presale.no_show_slash_bps = 0;
+    presale.open_user_infos = 0;
+    presale.open_settlements = 0;
+    presale.version = ACCOUNT_VERSION;
+    presale.presale_id = ctx.accounts.global_state.total_presales;
+    presale.project = ctx.accounts.project.key();
+
+    presale.vesting_percentages = [
+        VESTING_FIRST_RELEASE_PERCENTAGE,
+        VESTING_SECOND_RELEASE_PERCENTAGE,
+        VESTING_THIRD_RELEASE_PERCENTAGE,
+    ];
+    presale.allowlist_enabled = false;
+    presale.listing_deposit = 0;
+
     presale.bump = ctx.bumps.presale;
 
-    // Transfer tokens from creator to presale token account
-    let cpi_accounts = Transfer {
-        from: ctx.accounts.creator_token_account.to_account_info(),
-        to: ctx.accounts.presale_token_account.to_account_info(),
-        authority: ctx.accounts.creator.to_account_info(),
-    };
+    // Rounds for the same mint and creator share one project
+    let project = &mut ctx.accounts.project;
+    init_project(
+        project,
+        ctx.accounts.mint_of_token_being_sold.key(),
+        ctx.accounts.creator.key(),
+        ctx.bumps.project,
+    );
+
+    presale.previous_round_end_time =
+        schedule_round(project, params.registration_start_time, params.end_time)?;
 
     let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    let token_deposit = presale.token_deposit()?;
+
+    match &ctx.accounts.project_vault {
+        // Fund the round from supply already deposited in the project
+        Some(project_vault) => {
+            allocate_project_supply(project, token_deposit)?;
+
+            let seeds = &[
+                SEED_PREFIX_PROJECT,
+                project.mint.as_ref(),
+                project.creator.as_ref(),
+                &[project.bump],
+            ];
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = Transfer {
+                from: project_vault.to_account_info(),
+                to: ctx.accounts.presale_token_account.to_account_info(),
+                authority: project.to_account_info(),
+            };
+
+            token::transfer(
+                CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+                token_deposit,
+            )?;
+        }
+        // Transfer tokens from creator to presale token account
+        None => {
+            project.tokens_deposited = project.tokens_deposited.checked_add(token_deposit).unwrap();
+            project.tokens_allocated = project.tokens_allocated.checked_add(token_deposit).unwrap();
+
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.creator_token_account.to_account_info(),
+                to: ctx.accounts.presale_token_account.to_account_info(),
+                authority: ctx.accounts.creator.to_account_info(),
+            };
+
+            token::transfer(CpiContext::new(cpi_program, cpi_accounts), token_deposit)?;
+        }
+    }
+
+    charge_application_fee(&ctx)?;
+
+    // Held by the presale until the admin approves or rejects it
+    let listing_deposit = ctx.accounts.global_state.listing_deposit;
+    if listing_deposit > 0 {
+        invoke(
+            &system_instruction::transfer(
+                &ctx.accounts.creator.key(),
+                &ctx.accounts.presale.key(),
+                listing_deposit,
+            ),
+            &[
+                ctx.accounts.creator.to_account_info(),
+                ctx.accounts.presale.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+        ctx.accounts.presale.listing_deposit = listing_deposit;
+    }
 
-    token::transfer(cpi_ctx, tokens_for_sale)?;
+    // Human-readable details, editable until approval
+    let presale_metadata = &mut ctx.accounts.presale_metadata;
+    presale_metadata.presale = ctx.accounts.presale.key();
+    presale_metadata.bump = ctx.bumps.presale_metadata;
+    set_presale_metadata(presale_metadata, metadata)?;
 
     // Update global state
     let global_state = &mut ctx.accounts.global_state;
     global_state.total_presales += 1;
+    global_state.presales_by_status[STATUS_PENDING as usize] += 1;
 
     msg!("Presale created successfully");
 
     Ok(())
 }
 
+/// Lets the creator correct a pending presale. Times are re-validated and
+/// the deposit follows `tokens_for_sale` plus `tokens_for_liquidity`: extra
+/// tokens come from the creator, surplus tokens go back to them.
+pub fn update_presale(ctx: Context<UpdatePresale>, params: UpdatePresaleParams) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let project = &mut ctx.accounts.project;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // Auction and curve modes derive token_price from their own terms
+    require!(
+        presale.sale_mode == SALE_MODE_FIXED_PRICE || presale.sale_mode == SALE_MODE_OVERFLOW,
+        IdoError::InvalidSaleMode
+    );
+
+    validate_presale_times(
+        params.registration_start_time,
+        params.registration_end_time,
+        params.start_time,
+        params.end_time,
+        current_time,
+    )?;
+
+    validate_listing_price(
+        params.token_price,
+        params.listing_price,
+        &ctx.accounts.global_state,
+    )?;
+
+    validate_liquidity_reserve(
+        params.tokens_for_sale,
+        params.token_price,
+        params.tokens_for_liquidity,
+        params.listing_price,
+    )?;
+
+    require!(
+        params.registration_start_time > presale.previous_round_end_time,
+        IdoError::RoundOverlap
+    );
+
+    let new_sale_end = if presale.fcfs_enabled {
+        params.end_time.max(presale.fcfs_end_time)
+    } else {
+        params.end_time
+    };
+
+    // Only the latest round can move its end; earlier ones can't run into the next
+    if project.last_round_end_time == presale.sale_end_time() {
+        project.last_round_end_time = new_sale_end;
+    } else {
+        require!(new_sale_end <= presale.sale_end_time(), IdoError::RoundOverlap);
+    }
+
+    // A configured FCFS round still has to follow the main window
+    if presale.fcfs_enabled {
+        require!(
+            presale.fcfs_start_time >= params.end_time,
+            IdoError::InvalidTimeSetup
+        );
+    }
+
+    // Release times keep their offsets from the end of the sale
+    let end_time_shift = params.end_time.checked_sub(presale.end_time).unwrap();
+    presale.first_release_time = presale.first_release_time.checked_add(end_time_shift).unwrap();
+    presale.second_release_time = presale.second_release_time.checked_add(end_time_shift).unwrap();
+    presale.third_release_time = presale.third_release_time.checked_add(end_time_shift).unwrap();
+
+    let previous_deposit = presale.token_deposit()?;
+    let new_deposit = params.tokens_for_sale.checked_add(params.tokens_for_liquidity).unwrap();
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+
+    if new_deposit > previous_deposit {
+        let top_up = new_deposit - previous_deposit;
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.creator_token_account.to_account_info(),
+            to: ctx.accounts.presale_token_account.to_account_info(),
+            authority: ctx.accounts.creator.to_account_info(),
+        };
+
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), top_up)?;
+
+        project.tokens_deposited = project.tokens_deposited.checked_add(top_up).unwrap();
+        project.tokens_allocated = project.tokens_allocated.checked_add(top_up).unwrap();
+    } else if new_deposit < previous_deposit {
+        let surplus = previous_deposit - new_deposit;
+
+        let presale_id = presale.id_seed();
+        let seeds = &[
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale_id.as_ref(),
+            &[presale.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.presale_token_account.to_account_info(),
+            to: ctx.accounts.creator_token_account.to_account_info(),
+            authority: presale.to_account_info(),
+        };
+
+        token::transfer(
+            CpiContext::new_with_signer(cpi_program, cpi_accounts, signer),
+            surplus,
+        )?;
+
+        project.tokens_deposited = project.tokens_deposited.saturating_sub(surplus);
+        project.tokens_allocated = project.tokens_allocated.saturating_sub(surplus);
+    }
+
+    let (tier1_allocation, tier2_allocation, tier3_allocation) =
+        calculate_presale_tier_allocations(params.tokens_for_sale);
+
+    presale.tokens_for_sale = params.tokens_for_sale;
+    presale.tokens_for_liquidity = params.tokens_for_liquidity;
+    presale.tier1_allocation = tier1_allocation;
+    presale.tier2_allocation = tier2_allocation;
+    presale.tier3_allocation = tier3_allocation;
+    presale.token_price = params.token_price;
+    presale.listing_price = params.listing_price;
+    presale.start_time = params.start_time;
+    presale.end_time = params.end_time;
+    presale.registration_start_time = params.registration_start_time;
+    presale.registration_end_time = params.registration_end_time;
+
+    msg!("Presale updated successfully");
+
+    Ok(())
+}
+
 pub fn approve_presale(ctx: Context<ApprovePresale>) -> Result<()> {
     let presale = &mut ctx.accounts.presale;
 
@@ -118,57 +544,219 @@ pub fn approve_presale(ctx: Context<ApprovePresale>) -> Result<()> {
     );
 
     // Update status to approved
-    presale.status = STATUS_APPROVED;
-
-    // Update global state
-    let global_state = &mut ctx.accounts.global_state;
-    global_state.active_presales += 1;
+    set_presale_status(presale, &mut ctx.accounts.global_state, STATUS_APPROVED);
+
+    // Approved applications get their deposit back
+    if presale.listing_deposit > 0 {
+        transfer_lamports_from_pda(
+            &presale.to_account_info(),
+            &ctx.accounts.creator.to_account_info(),
+            presale.listing_deposit,
+        )?;
+        presale.listing_deposit = 0;
+    }
 
     msg!("Presale approved successfully");
 
     Ok(())
 }
 
+/// Turns down a pending application: the deposited tokens go back to the
+/// creator and the presale accounts are closed so they can apply again.
+/// `reason` is an off-chain reason code carried in the emitted event.
+pub fn reject_presale(ctx: Context<RejectPresale>, reason: u8) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let project = &mut ctx.accounts.project;
+
+    require!(
+        presale.sale_mode != SALE_MODE_BATCH_AUCTION || ctx.accounts.order_book.is_some(),
+        IdoError::PresaleNotClosable
+    );
+
+    set_presale_status(presale, &mut ctx.accounts.global_state, STATUS_CANCELLED);
+
+    // The deposit is forfeited to the treasury
+    if presale.listing_deposit > 0 {
+        transfer_lamports_from_pda(
+            &presale.to_account_info(),
+            &ctx.accounts.treasury_wallet.to_account_info(),
+            presale.listing_deposit,
+        )?;
+        presale.listing_deposit = 0;
+    }
+
+    // The rejected supply leaves the project, and its window is free again
+    let token_deposit = presale.token_deposit()?;
+    project.tokens_deposited = project.tokens_deposited.saturating_sub(token_deposit);
+    project.tokens_allocated = project.tokens_allocated.saturating_sub(token_deposit);
+    if project.last_round_end_time == presale.sale_end_time() {
+        project.last_round_end_time = presale.previous_round_end_time;
+    }
+
+    let returned = return_presale_tokens(
+        presale,
+        &ctx.accounts.presale_token_account,
+        &ctx.accounts.creator_token_account,
+        &ctx.accounts.creator.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+    )?;
+
+    emit!(PresaleRejected {
+        presale: presale.key(),
+        creator: presale.creator,
+        presale_id: presale.presale_id,
+        reason,
+    });
+
+    msg!("Presale rejected, {} tokens returned to the creator", returned);
+
+    Ok(())
+}
+
+/// Opens an approved presale once its start time has passed. Permissionless.
+/// Anything gated on the approved status also checks `start_time`, so it
+/// doesn't matter how late this runs.
+pub fn start_presale(ctx: Context<UpdatePresaleStatus>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.status == STATUS_APPROVED,
+        IdoError::InvalidPresaleStatus
+    );
+
+    require!(
+        current_time >= presale.start_time,
+        IdoError::PresaleNotStarted
+    );
+
+    set_presale_status(presale, &mut ctx.accounts.global_state, STATUS_LIVE);
+
+    msg!("Presale is live");
+
+    Ok(())
+}
+
+/// Completes a live presale once the sale window, including any FCFS
+/// round, has closed. Permissionless. Settlement checks the sale times
+/// itself, and vesting releases are fixed times, so running this late only
+/// delays claims and listing.
+pub fn finalize_presale(ctx: Context<UpdatePresaleStatus>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        presale.status == STATUS_LIVE,
+        IdoError::InvalidPresaleStatus
+    );
+
+    require!(
+        current_time > presale.sale_end_time(),
+        IdoError::PresaleNotCompleted
+    );
+
+    // A curve completes when it graduates; until then holders can sell back
+    require!(
+        presale.sale_mode != SALE_MODE_BONDING_CURVE,
+        IdoError::CurveNotGraduated
+    );
+
+    set_presale_status(presale, &mut ctx.accounts.global_state, STATUS_COMPLETED);
+
+    msg!("Presale completed");
+
+    Ok(())
+}
+
 pub fn register_for_presale(ctx: Context<RegisterForPresale>) -> Result<()> {
-    let presale = &ctx.accounts.presale;
-    let user_stake = &ctx.accounts.user_stake;
+    let presale = &mut ctx.accounts.presale;
     let user_info = &mut ctx.accounts.user_info;
 
-    // Check if user is eligible for any tier
-    check_tier_eligibility(user_stake, presale)?;
+    // Without a stake summary there is no delegate to act for the user
+    if ctx.accounts.user_stake_summary.is_none() {
+        require!(
+            ctx.accounts.authority.key() == ctx.accounts.user.key(),
+            IdoError::Unauthorized
+        );
+    }
+
+    // Allowlisted rounds grant a fixed allocation, others are tier-based
+    let (allocation, registered_tier) = if presale.allowlist_enabled {
+        let entry = ctx
+            .accounts
+            .allowlist_entry
+            .as_ref()
+            .ok_or(IdoError::NotOnAllowlist)?;
+
+        check_registration_open(presale)?;
+
+        (entry.allocation, 0)
+    } else {
+        let user_stake_summary = ctx
+            .accounts
+            .user_stake_summary
+            .as_ref()
+            .ok_or(IdoError::InsufficientTierQualification)?;
+
+        // Check if user is eligible for any tier
+        let tier = check_tier_eligibility(user_stake_summary, presale)?;
+
+        (0, tier) // Allocation will be calculated during purchase
+    };
 
     // Initialize user presale info
     user_info.user = ctx.accounts.user.key();
     user_info.presale = presale.key();
-    user_info.allocation = 0; // Will be calculated during purchase
+    user_info.allocation = allocation;
     user_info.purchased = 0;
     user_info.claimed = 0;
     user_info.first_claim_processed = false;
     user_info.second_claim_processed = false;
     user_info.third_claim_processed = false;
+    user_info.fcfs_purchased = 0;
+    user_info.sol_committed = 0;
+    user_info.settled = false;
+    user_info.penalized = false;
+    user_info.registered_tier = registered_tier;
+    user_info.rent_payer = ctx.accounts.authority.key();
+    user_info.version = ACCOUNT_VERSION;
     user_info.bump = ctx.bumps.user_info;
 
+    presale.open_user_infos = presale.open_user_infos.checked_add(1).unwrap();
+
     msg!("User registered for presale successfully");
 
     Ok(())
 }
 
-pub fn buy_tokens(ctx: Context<BuyTokens>, mut amount: u64) -> Result<()> {
-    // Create a copy of the key before mutable borrow
-
-    let user_key = ctx.accounts.user.key();
+pub fn buy_tokens(ctx: Context<BuyTokens>, amount: u64) -> Result<()> {
+    // The user or their delegate pays for the purchase
+    let authority_key = ctx.accounts.authority.key();
 
     let presale = &mut ctx.accounts.presale;
-    let user_stake = &ctx.accounts.user_stake;
     let user_info = &mut ctx.accounts.user_info;
     let current_time = Clock::get()?.unix_timestamp;
 
+    // Without a stake summary there is no delegate to act for the user
+    if ctx.accounts.user_stake_summary.is_none() {
+        require!(
+            authority_key == ctx.accounts.user.key(),
+            IdoError::Unauthorized
+        );
+    }
+
     // Ensure presale is live
     require!(
         presale.status == STATUS_LIVE,
         IdoError::InvalidPresaleStatus
     );
 
+    require!(
+        presale.sale_mode == SALE_MODE_FIXED_PRICE
+            || presale.sale_mode == SALE_MODE_DUTCH_AUCTION,
+        IdoError::InvalidSaleMode
+    );
+
     // Ensure presale is within time bounds
     require!(
         current_time >= presale.start_time,
@@ -177,43 +765,73 @@ pub fn buy_tokens(ctx: Context<BuyTokens>, mut amount: u64) -> Result<()> {
 
     require!(current_time <= presale.end_time, IdoError::PresaleEnded);
 
+    // Dutch auctions buy at the decayed price, fixed sales at token_price
+    let price = if presale.sale_mode == SALE_MODE_DUTCH_AUCTION {
+        get_current_auction_price(presale, current_time)
+    } else {
+        presale.token_price
+    };
+
     // Calculate SOL amount needed
-    let sol_amount = amount.checked_mul(presale.token_price).unwrap();
+    let sol_amount = amount.checked_mul(price).unwrap();
+
+    // Allowlisted buyers are bound by their own allocation, not tier pools
+    let user_tier = if presale.allowlist_enabled {
+        require!(
+            user_info.purchased.checked_add(amount).unwrap() <= user_info.allocation,
+            IdoError::InsufficientAllocation
+        );
 
-    // Check if user can purchase based on tier
-    let user_tier = user_stake.tier;
-    let mut available_allocation = 0;
+        require!(
+            presale.tokens_sold.checked_add(amount).unwrap() <= presale.tokens_for_sale,
+            IdoError::InsufficientTokensRemaining
+        );
 
-    // Try to buy from tier 1 first if user is tier 1 or higher
-    if can_purchase_from_tier(1, user_tier, presale) {
-        available_allocation += get_available_allocation_for_tier(1, presale)?;
-    }
+        0
+    } else {
+        let user_stake_summary = ctx
+            .accounts
+            .user_stake_summary
+            .as_ref()
+            .ok_or(IdoError::InsufficientTierQualification)?;
+
+        // Check if user can purchase based on the tier across all stake positions
+        let user_tier = get_summary_tier(user_stake_summary, current_time)?;
+        let mut available_allocation = 0;
+
+        // Try to buy from tier 1 first if user is tier 1 or higher
+        if can_purchase_from_tier(1, user_tier, presale) {
+            available_allocation += get_available_allocation_for_tier(1, presale)?;
+        }
 
-    // Then try tier 2 if user is tier 2 or higher
-    if can_purchase_from_tier(2, user_tier, presale) {
-        available_allocation += get_available_allocation_for_tier(2, presale)?;
-    }
+        // Then try tier 2 if user is tier 2 or higher
+        if can_purchase_from_tier(2, user_tier, presale) {
+            available_allocation += get_available_allocation_for_tier(2, presale)?;
+        }
 
-    // Then try tier 3 if user is tier 3
-    if can_purchase_from_tier(3, user_tier, presale) {
-        available_allocation += get_available_allocation_for_tier(3, presale)?;
-    }
+        // Then try tier 3 if user is tier 3
+        if can_purchase_from_tier(3, user_tier, presale) {
+            available_allocation += get_available_allocation_for_tier(3, presale)?;
+        }
 
-    // Ensure user has enough allocation
-    require!(
-        available_allocation >= amount,
-        IdoError::InsufficientAllocation
-    );
+        // Ensure user has enough allocation
+        require!(
+            available_allocation >= amount,
+            IdoError::InsufficientAllocation
+        );
+
+        user_tier
+    };
 
     // Store the account info before using it
     let presale_key = presale.key();
     let presale_info = presale.to_account_info();
 
-    // Transfer SOL from user to presale account
+    // Transfer SOL from the paying authority to presale account
     invoke(
-        &system_instruction::transfer(&user_key, &presale_key, sol_amount),
+        &system_instruction::transfer(&authority_key, &presale_key, sol_amount),
         &[
-            ctx.accounts.user.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
             presale_info,
             ctx.accounts.system_program.to_account_info(),
         ],
@@ -224,33 +842,35 @@ pub fn buy_tokens(ctx: Context<BuyTokens>, mut amount: u64) -> Result<()> {
     presale.sol_raised = presale.sol_raised.checked_add(sol_amount).unwrap();
 
     // Update tier allocations
+    let mut remaining = amount;
+
     if can_purchase_from_tier(1, user_tier, presale) {
         let tier1_purchase = std::cmp::min(
-            amount,
+            remaining,
             presale.tier1_allocation.saturating_sub(presale.tier1_sold),
         );
 
         if tier1_purchase > 0 {
             presale.tier1_sold = presale.tier1_sold.checked_add(tier1_purchase).unwrap();
-            amount = amount.saturating_sub(tier1_purchase);
+            remaining = remaining.saturating_sub(tier1_purchase);
         }
     }
 
-    if amount > 0 && can_purchase_from_tier(2, user_tier, presale) {
+    if remaining > 0 && can_purchase_from_tier(2, user_tier, presale) {
         let tier2_purchase = std::cmp::min(
-            amount,
+            remaining,
             presale.tier2_allocation.saturating_sub(presale.tier2_sold),
         );
 
         if tier2_purchase > 0 {
             presale.tier2_sold = presale.tier2_sold.checked_add(tier2_purchase).unwrap();
-            amount = amount.saturating_sub(tier2_purchase);
+            remaining = remaining.saturating_sub(tier2_purchase);
         }
     }
 
-    if amount > 0 && can_purchase_from_tier(3, user_tier, presale) {
+    if remaining > 0 && can_purchase_from_tier(3, user_tier, presale) {
         let tier3_purchase = std::cmp::min(
-            amount,
+            remaining,
             presale.tier3_allocation.saturating_sub(presale.tier3_sold),
         );
 
@@ -260,61 +880,182 @@ pub fn buy_tokens(ctx: Context<BuyTokens>, mut amount: u64) -> Result<()> {
     }
 
     // Update user info
-    user_info.allocation = user_info.allocation.checked_add(amount).unwrap();
+    if !presale.allowlist_enabled {
+        user_info.allocation = user_info.allocation.checked_add(amount).unwrap();
+    }
     user_info.purchased = user_info.purchased.checked_add(amount).unwrap();
 
+    // Auction buyers are settled at the clearing price after the window.
+    // The over-commit goes back to whoever paid, so one wallet pays for all
+    // of a user's auction purchases.
+    if presale.sale_mode == SALE_MODE_DUTCH_AUCTION {
+        if user_info.sol_committed == 0 {
+            presale.open_settlements = presale.open_settlements.checked_add(1).unwrap();
+            user_info.auction_payer = authority_key;
+        } else {
+            require!(
+                user_info.refund_recipient() == authority_key,
+                IdoError::AuctionPayerMismatch
+            );
+        }
+        user_info.sol_committed = user_info.sol_committed.checked_add(sol_amount).unwrap();
+        presale.auction_last_price = price;
+    }
+
     msg!("User purchased {} tokens successfully", amount);
 
     Ok(())
 }
 
-pub fn list_token(ctx: Context<ListToken>) -> Result<()> {
+pub fn configure_fcfs(
+    ctx: Context<ConfigureFcfs>,
+    fcfs_start_time: i64,
+    fcfs_end_time: i64,
+    fcfs_wallet_cap: u64,
+) -> Result<()> {
     let presale = &mut ctx.accounts.presale;
 
-    // Store account info before mutably borrowing presale
-    let presale_info = presale.to_account_info();
+    require!(
+        presale.sale_mode == SALE_MODE_FIXED_PRICE,
+        IdoError::InvalidSaleMode
+    );
 
-    // Ensure presale is not already listed
-    require!(!presale.is_listed, IdoError::TokenAlreadyListed);
+    // FCFS round must follow the guaranteed allocation window
+    require!(
+        fcfs_start_time >= presale.end_time,
+        IdoError::InvalidTimeSetup
+    );
 
-    // Calculate the amount of SOL to add to liquidity (80% of SOL raised)
-    let sol_to_liquidity = presale
-        .sol_raised
-        .checked_mul(80)
-        .unwrap()
-        .checked_div(100)
-        .unwrap();
+    require!(fcfs_start_time < fcfs_end_time, IdoError::InvalidTimeSetup);
 
-    // Calculate the amount of tokens to add to liquidity (20% of tokens sold)
-    let tokens_to_liquidity = presale
-        .tokens_sold
-        .checked_mul(20)
-        .unwrap()
-        .checked_div(100)
-        .unwrap();
+    require!(fcfs_wallet_cap > 0, IdoError::InsufficientAllocation);
 
-    // Transfer SOL to the liquidity pool
-    let sol_transfer_ix = system_instruction::transfer(
-        &presale.key(),
-        &ctx.accounts.liquidity_pool.key(),
-        sol_to_liquidity,
+    // The FCFS round extends the sale, so the same overlap rule as
+    // update_presale applies to its end
+    let project = &mut ctx.accounts.project;
+    let new_sale_end = fcfs_end_time.max(presale.end_time);
+    if project.last_round_end_time == presale.sale_end_time() {
+        project.last_round_end_time = new_sale_end;
+    } else {
+        require!(new_sale_end <= presale.sale_end_time(), IdoError::RoundOverlap);
+    }
+
+    presale.fcfs_enabled = true;
+    presale.fcfs_start_time = fcfs_start_time;
+    presale.fcfs_end_time = fcfs_end_time;
+    presale.fcfs_wallet_cap = fcfs_wallet_cap;
+
+    msg!("FCFS round configured successfully");
+
+    Ok(())
+}
+
+/// Checks an FCFS purchase against the round window, the unsold supply and
+/// the wallet cap. Returns the wallet's FCFS total after the purchase.
+pub fn check_fcfs_purchase(
+    presale: &Presale,
+    fcfs_purchased: u64,
+    amount: u64,
+    current_time: i64,
+) -> Result<u64> {
+    require!(presale.fcfs_enabled, IdoError::FcfsNotEnabled);
+
+    // Ensure FCFS round is within time bounds
+    require!(
+        current_time >= presale.fcfs_start_time,
+        IdoError::FcfsNotStarted
+    );
+
+    require!(current_time <= presale.fcfs_end_time, IdoError::FcfsEnded);
+
+    // Only the leftover of the guaranteed round is up for grabs
+    let tokens_remaining = presale.tokens_for_sale.saturating_sub(presale.tokens_sold);
+    require!(
+        tokens_remaining >= amount,
+        IdoError::InsufficientTokensRemaining
     );
 
+    let wallet_total = fcfs_purchased.checked_add(amount).unwrap();
+    require!(
+        wallet_total <= presale.fcfs_wallet_cap,
+        IdoError::FcfsWalletCapExceeded
+    );
+
+    Ok(wallet_total)
+}
+
+pub fn buy_tokens_fcfs(ctx: Context<BuyTokensFcfs>, amount: u64) -> Result<()> {
+    let user_key = ctx.accounts.user.key();
+
+    let presale = &mut ctx.accounts.presale;
+    let user_info = &mut ctx.accounts.user_info;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    let wallet_total =
+        check_fcfs_purchase(presale, user_info.fcfs_purchased, amount, current_time)?;
+
+    // Calculate SOL amount needed
+    let sol_amount = amount.checked_mul(presale.token_price).unwrap();
+
+    let presale_key = presale.key();
+    let presale_info = presale.to_account_info();
+
+    // Transfer SOL from user to presale account
     invoke(
-        &sol_transfer_ix,
+        &system_instruction::transfer(&user_key, &presale_key, sol_amount),
         &[
-            presale_info.clone(),
-            ctx.accounts.liquidity_pool.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            presale_info,
             ctx.accounts.system_program.to_account_info(),
         ],
     )?;
 
+    // Update presale info
+    presale.tokens_sold = presale.tokens_sold.checked_add(amount).unwrap();
+    presale.sol_raised = presale.sol_raised.checked_add(sol_amount).unwrap();
+
+    // Update user info
+    user_info.allocation = user_info.allocation.checked_add(amount).unwrap();
+    user_info.purchased = user_info.purchased.checked_add(amount).unwrap();
+    user_info.fcfs_purchased = wallet_total;
+
+    msg!("User purchased {} tokens in the FCFS round", amount);
+
+    Ok(())
+}
+
+pub fn list_token(ctx: Context<ListToken>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+
+    // Store account info before mutably borrowing presale
+    let presale_info = presale.to_account_info();
+
+    // Ensure presale is not already listed
+    require!(!presale.is_listed, IdoError::TokenAlreadyListed);
+
+    require!(presale.listing_price > 0, IdoError::InvalidListingPrice);
+
+    // sol_raised is only final once every commitment and bid is settled
+    require!(presale.open_settlements == 0, IdoError::SettlementsPending);
+
+    let (sol_to_liquidity, tokens_to_liquidity) = calculate_listing_amounts(presale);
+
+    // Transfer SOL to the liquidity pool
+    transfer_lamports_from_pda(
+        &presale_info,
+        &ctx.accounts.liquidity_pool.to_account_info(),
+        sol_to_liquidity,
+    )?;
+
     // Transfer tokens to the liquidity pool
 
+    let presale_id = presale.id_seed();
+
     let seeds = &[
         SEED_PREFIX_PRESALE,
         presale.mint_of_token_being_sold.as_ref(),
         presale.creator.as_ref(),
+        presale_id.as_ref(),
         &[presale.bump],
     ];
     let signer = &[&seeds[..]];
@@ -341,42 +1082,524 @@ pub fn list_token(ctx: Context<ListToken>) -> Result<()> {
     Ok(())
 }
 
+/// Pays the protocol fee out once every commitment is settled, so
+/// `sol_raised` is final and refunds are never short. The stakers' share is
+/// split between all staking pools by their weighted power and streamed out
+/// by each pool; `remaining_accounts` holds every pool (writable) followed
+/// by its mint's config.
+pub fn collect_protocol_fee(ctx: Context<CollectProtocolFee>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let global_state = &ctx.accounts.global_state;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    require!(
+        !presale.protocol_fee_collected,
+        IdoError::ProtocolFeeAlreadyCollected
+    );
+
+    require!(presale.open_settlements == 0, IdoError::SettlementsPending);
+
+    require!(
+        ctx.remaining_accounts.len() as u64 == global_state.staking_pool_count * 2,
+        IdoError::InvalidFeeAccounts
+    );
+
+    let mut pools = Vec::with_capacity(global_state.staking_pool_count as usize);
+    let mut pool_keys = Vec::with_capacity(global_state.staking_pool_count as usize);
+    let mut total_power: u64 = 0;
+
+    for accounts in ctx.remaining_accounts.chunks(2) {
+        let (pool_info, config_info) = (&accounts[0], &accounts[1]);
+
+        require!(
+            pool_info.owner == &crate::ID
+                && pool_info.is_writable
+                && config_info.owner == &crate::ID,
+            IdoError::InvalidFeeAccounts
+        );
+
+        let pool = {
+            let data = pool_info.try_borrow_data()?;
+            StakingPool::try_deserialize(&mut &data[..])?
+        };
+        let config = {
+            let data = config_info.try_borrow_data()?;
+            StakingMintConfig::try_deserialize(&mut &data[..])?
+        };
+
+        require!(
+            config.mint == pool.staking_token_mint,
+            IdoError::InvalidFeeAccounts
+        );
+
+        let power = get_pool_power(&pool, &config);
+        total_power = total_power.checked_add(power).unwrap();
+        pool_keys.push(pool_info.key());
+        pools.push((pool_info, pool, power));
+    }
+
+    // Reject duplicates so a pool can't take two shares
+    pool_keys.sort();
+    pool_keys.dedup();
+    require!(
+        pool_keys.len() == pools.len(),
+        IdoError::InvalidFeeAccounts
+    );
+
+    let protocol_fee = presale
+        .sol_raised
+        .checked_mul(PROTOCOL_FEE_PERCENTAGE as u64)
+        .unwrap()
+        .checked_div(100)
+        .unwrap();
+
+    // Nobody to share with if nothing is staked
+    let staker_share = if total_power > 0 {
+        protocol_fee
+            .checked_mul(global_state.staker_fee_share_bps as u64)
+            .unwrap()
+            .checked_div(BPS_DENOMINATOR)
+            .unwrap()
+    } else {
+        0
+    };
+
+    let presale_info = presale.to_account_info();
+    let mut distributed: u64 = 0;
+
+    for (pool_info, mut pool, power) in pools {
+        let pool_share = (staker_share as u128)
+            .checked_mul(power as u128)
+            .unwrap()
+            .checked_div(total_power.max(1) as u128)
+            .unwrap() as u64;
+
+        if pool_share == 0 {
+            continue;
+        }
+
+        transfer_lamports_from_pda(&presale_info, pool_info, pool_share)?;
+
+        update_pool_rewards(&mut pool, current_time);
+        distribute_sol_rewards(&mut pool, pool_share, current_time);
+
+        let mut data = pool_info.try_borrow_mut_data()?;
+        pool.try_serialize(&mut &mut data[..])?;
+
+        distributed = distributed.checked_add(pool_share).unwrap();
+    }
+
+    // Rounding dust goes to the treasury
+    let staker_share = distributed;
+    let treasury_share = protocol_fee.checked_sub(staker_share).unwrap();
+
+    if treasury_share > 0 {
+        transfer_lamports_from_pda(
+            &presale_info,
+            &ctx.accounts.treasury_wallet.to_account_info(),
+            treasury_share,
+        )?;
+    }
+
+    presale.protocol_fee_collected = true;
+
+    msg!(
+        "Protocol fee collected: {} lamports to stakers, {} lamports to treasury",
+        staker_share,
+        treasury_share
+    );
+
+    Ok(())
+}
+
+/// Whether the user is owed nothing more: every purchased token is claimed
+/// and any SOL commitment is settled. Registrations that took all three
+/// releases count as claimed, since releases once rounded down on their own.
+pub fn is_user_presale_info_settled(user_info: &UserPresaleInfo) -> bool {
+    let all_releases_claimed = user_info.first_claim_processed
+        && user_info.second_claim_processed
+        && user_info.third_claim_processed;
+
+    (user_info.claimed >= user_info.purchased || all_releases_claimed)
+        && (user_info.sol_committed == 0 || user_info.settled)
+}
+
+/// Closes a registration once the user is owed nothing more. Rent goes
+/// back to whoever paid for the account.
+pub fn close_user_presale_info(ctx: Context<CloseUserPresaleInfo>) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+    let user_info = &ctx.accounts.user_info;
+
+    require!(
+        is_user_presale_info_settled(user_info),
+        IdoError::UserInfoNotSettled
+    );
+
+    // No-shows can't dodge the penalty by closing first
+    require!(
+        presale.status == STATUS_CANCELLED || !is_penalizable_no_show(presale, user_info),
+        IdoError::UserInfoNotSettled
+    );
+
+    presale.open_user_infos = presale.open_user_infos.saturating_sub(1);
+
+    msg!("User presale info closed");
+
+    Ok(())
+}
+
+/// Sends every token left in the presale token account to the creator and
+/// closes it, refunding its rent to the creator. Returns the amount sent.
+fn return_presale_tokens<'info>(
+    presale: &Account<'info, Presale>,
+    presale_token_account: &Account<'info, TokenAccount>,
+    creator_token_account: &Account<'info, TokenAccount>,
+    creator: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<u64> {
+    let presale_id = presale.id_seed();
+
+    let seeds = &[
+        SEED_PREFIX_PRESALE,
+        presale.mint_of_token_being_sold.as_ref(),
+        presale.creator.as_ref(),
+        presale_id.as_ref(),
+        &[presale.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let leftover = presale_token_account.amount;
+
+    if leftover > 0 {
+        let cpi_accounts = Transfer {
+            from: presale_token_account.to_account_info(),
+            to: creator_token_account.to_account_info(),
+            authority: presale.to_account_info(),
+        };
+
+        token::transfer(
+            CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer),
+            leftover,
+        )?;
+    }
+
+    let cpi_accounts = CloseAccount {
+        account: presale_token_account.to_account_info(),
+        destination: creator.clone(),
+        authority: presale.to_account_info(),
+    };
+
+    token::close_account(CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer))?;
+
+    Ok(leftover)
+}
+
+/// Whether the presale has run its course and every registration is closed.
+/// Legacy presales sign with their id-less seeds like every other
+/// instruction.
+pub fn is_presale_closable(presale: &Presale) -> bool {
+    let is_finished = match presale.status {
+        STATUS_COMPLETED => presale.is_listed && presale.protocol_fee_collected,
+        STATUS_CANCELLED => true,
+        _ => false,
+    };
+
+    is_finished && presale.open_user_infos == 0
+}
+
+/// Closes a finished presale. Requires every registration to be closed and,
+/// for completed sales, the token listed and the protocol fee collected.
+/// Leftover tokens, the remaining SOL and all rent go to the creator.
+pub fn close_presale(ctx: Context<ClosePresale>) -> Result<()> {
+    let presale = &ctx.accounts.presale;
+
+    require!(is_presale_closable(presale), IdoError::PresaleNotClosable);
+
+    require!(
+        presale.sale_mode != SALE_MODE_BATCH_AUCTION || ctx.accounts.order_book.is_some(),
+        IdoError::PresaleNotClosable
+    );
+
+    let leftover = return_presale_tokens(
+        presale,
+        &ctx.accounts.presale_token_account,
+        &ctx.accounts.creator_token_account,
+        &ctx.accounts.creator.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+    )?;
+
+    msg!("Presale closed, {} leftover tokens returned", leftover);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreatePresale<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + Project::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_PROJECT,
+            mint_of_token_being_sold.key().as_ref(),
+            creator.key().as_ref(),
+        ],
+        bump
+    )]
+    pub project: Box<Account<'info, Project>>,
+
+    // Only when the round is funded from the project's deposited supply
+    #[account(
+        mut,
+        constraint = project_vault.owner == project.key(),
+        constraint = project_vault.mint == mint_of_token_being_sold.key()
+    )]
+    pub project_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + Presale::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            mint_of_token_being_sold.key().as_ref(),
+            creator.key().as_ref(),
+            global_state.total_presales.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub presale: Account<'info, Presale>,
+
+    pub creator_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        token::mint = mint_of_token_being_sold,
+        token::authority = presale,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            mint_of_token_being_sold.key().as_ref(),
+            creator.key().as_ref(),
+            global_state.total_presales.to_le_bytes().as_ref(),
+            b"token_account"
+        ],
+        bump
+    )]
+    pub presale_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + PresaleMetadata::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_PRESALE_METADATA,
+            presale.key().as_ref()
+        ],
+        bump
+    )]
+    pub presale_metadata: Box<Account<'info, PresaleMetadata>>,
+
+    // Only when a SOL application fee is configured
+    #[account(
+        mut,
+        constraint = treasury_wallet.key() == global_state.treasury_wallet @ IdoError::Unauthorized
+    )]
+    /// CHECK: Validated against the treasury wallet in global state
+    pub treasury_wallet: Option<UncheckedAccount<'info>>,
+
+    // Only when the application fee is paid in the staking token
+    #[account(
+        mut,
+        constraint = creator_fee_token_account.owner == creator.key(),
+        constraint = creator_fee_token_account.mint == global_state.staking_token_mint
+    )]
+    pub creator_fee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        constraint = treasury_fee_token_account.owner == global_state.treasury_wallet,
+        constraint = treasury_fee_token_account.mint == global_state.staking_token_mint
+    )]
+    pub treasury_fee_token_account: Option<Box<Account<'info, TokenAccount>>>,
+
+    pub mint_of_token_being_sold: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePresale<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.creator == creator.key() @ IdoError::Unauthorized,
+        constraint = presale.status == STATUS_PENDING @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PROJECT,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+        ],
+        bump = project.bump,
+        constraint = project.key() == presale.project
+    )]
+    pub project: Account<'info, Project>,
+
+    #[account(
+        mut,
+        constraint = presale_token_account.key() == presale.presale_token_account
+    )]
+    pub presale_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == creator.key(),
+        constraint = creator_token_account.mint == presale.mint_of_token_being_sold
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ApprovePresale<'info> {
+    #[account(
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = creator.key() == presale.creator @ IdoError::Unauthorized
+    )]
+    /// CHECK: Receives the listing deposit; validated against the presale
+    pub creator: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
 #[derive(Accounts)]
-pub struct CreatePresale<'info> {
-    #[account(mut)]
-    pub creator: Signer<'info>,
+pub struct RejectPresale<'info> {
+    #[account(
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
 
     #[account(
-        init,
-        payer = creator,
-        space = 8 + std::mem::size_of::<Presale>(),
+        mut,
+        constraint = creator.key() == presale.creator @ IdoError::Unauthorized
+    )]
+    /// CHECK: Receives the rent of the closed accounts; validated against the presale
+    pub creator: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = creator,
         seeds = [
             SEED_PREFIX_PRESALE,
-            mint_of_token_being_sold.key().as_ref(),
-            creator.key().as_ref(),
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
         ],
-        bump
+        bump = presale.bump,
+        constraint = presale.status == STATUS_PENDING @ IdoError::InvalidPresaleStatus
     )]
     pub presale: Account<'info, Presale>,
 
-    pub creator_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = creator,
+        seeds = [
+            SEED_PREFIX_PRESALE_METADATA,
+            presale.key().as_ref()
+        ],
+        bump = presale_metadata.bump
+    )]
+    pub presale_metadata: Account<'info, PresaleMetadata>,
 
+    // Batch auctions only; required for them so the book's rent is returned
     #[account(
-        init,
-        payer = creator,
-        token::mint = mint_of_token_being_sold,
-        token::authority = presale,
+        mut,
+        close = creator,
         seeds = [
-            SEED_PREFIX_PRESALE,
-            mint_of_token_being_sold.key().as_ref(),
-            creator.key().as_ref(),
-            b"token_account"
+            SEED_PREFIX_ORDER_BOOK,
+            presale.key().as_ref()
         ],
-        bump
+        bump = order_book.bump
+    )]
+    pub order_book: Option<Account<'info, BatchOrderBook>>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PROJECT,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+        ],
+        bump = project.bump,
+        constraint = project.key() == presale.project
+    )]
+    pub project: Account<'info, Project>,
+
+    #[account(
+        mut,
+        constraint = presale_token_account.key() == presale.presale_token_account
     )]
     pub presale_token_account: Account<'info, TokenAccount>,
 
-    pub mint_of_token_being_sold: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == creator.key(),
+        constraint = creator_token_account.mint == presale.mint_of_token_being_sold
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
 
     #[account(
         mut,
@@ -385,23 +1608,25 @@ pub struct CreatePresale<'info> {
     )]
     pub global_state: Account<'info, GlobalState>,
 
+    #[account(
+        mut,
+        constraint = treasury_wallet.key() == global_state.treasury_wallet @ IdoError::Unauthorized
+    )]
+    /// CHECK: Receives the forfeited deposit; validated against global state
+    pub treasury_wallet: UncheckedAccount<'info>,
+
     pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ApprovePresale<'info> {
-    #[account(
-        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
-    )]
-    pub admin: Signer<'info>,
-
+pub struct UpdatePresaleStatus<'info> {
     #[account(
         mut,
         seeds = [
             SEED_PREFIX_PRESALE,
             presale.mint_of_token_being_sold.as_ref(),
             presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
         ],
         bump = presale.bump
     )]
@@ -428,6 +1653,7 @@ pub struct ManagePresale<'info> {
             SEED_PREFIX_PRESALE,
             presale.mint_of_token_being_sold.as_ref(),
             presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
         ],
         bump = presale.bump
     )]
@@ -443,8 +1669,12 @@ pub struct ManagePresale<'info> {
 
 #[derive(Accounts)]
 pub struct RegisterForPresale<'info> {
+    // Either the user or the delegate set on their stake summary
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub authority: Signer<'info>,
+
+    /// CHECK: Owner of the stake; checked against the stake summary
+    pub user: UncheckedAccount<'info>,
 
     #[account(
         mut,
@@ -452,27 +1682,40 @@ pub struct RegisterForPresale<'info> {
             SEED_PREFIX_PRESALE,
             presale.mint_of_token_being_sold.as_ref(),
             presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
         ],
         bump = presale.bump,
         constraint = presale.status == STATUS_APPROVED || presale.status == STATUS_LIVE @ IdoError::InvalidPresaleStatus
     )]
     pub presale: Account<'info, Presale>,
 
+    // Required for tier-based rounds; also carries the user's delegate
     #[account(
         seeds = [
-            SEED_PREFIX_USER_STAKE,
-            user.key().as_ref(),
-            global_state.staking_token_mint.as_ref()
+            SEED_PREFIX_STAKE_SUMMARY,
+            user.key().as_ref()
+        ],
+        bump = user_stake_summary.bump,
+        constraint = user_stake_summary.user == user.key(),
+        constraint = user_stake_summary.is_authorized(authority.key()) @ IdoError::Unauthorized
+    )]
+    pub user_stake_summary: Option<Account<'info, UserStakeSummary>>,
+
+    // Required for allowlisted rounds
+    #[account(
+        seeds = [
+            SEED_PREFIX_ALLOWLIST,
+            presale.key().as_ref(),
+            user.key().as_ref()
         ],
-        bump = user_stake.bump,
-        constraint = user_stake.user == user.key()
+        bump = allowlist_entry.bump
     )]
-    pub user_stake: Account<'info, UserStake>,
+    pub allowlist_entry: Option<Account<'info, AllowlistEntry>>,
 
     #[account(
         init,
-        payer = user,
-        space = 8 + std::mem::size_of::<UserPresaleInfo>(),
+        payer = authority,
+        space = 8 + UserPresaleInfo::INIT_SPACE,
         seeds = [
             SEED_PREFIX_USER_INFO,
             user.key().as_ref(),
@@ -492,8 +1735,12 @@ pub struct RegisterForPresale<'info> {
 }
 #[derive(Accounts)]
 pub struct BuyTokens<'info> {
+    // Either the user or the delegate set on their stake summary
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub authority: Signer<'info>,
+
+    /// CHECK: Owner of the stake; checked against the stake summary
+    pub user: UncheckedAccount<'info>,
 
     #[account(
         mut,
@@ -501,22 +1748,24 @@ pub struct BuyTokens<'info> {
             SEED_PREFIX_PRESALE,
             presale.mint_of_token_being_sold.as_ref(),
             presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
         ],
         bump = presale.bump,
         constraint = presale.status == STATUS_LIVE @ IdoError::InvalidPresaleStatus
     )]
     pub presale: Account<'info, Presale>,
 
+    // Required for tier-based rounds; also carries the user's delegate
     #[account(
         seeds = [
-            SEED_PREFIX_USER_STAKE,
-            user.key().as_ref(),
-            global_state.staking_token_mint.as_ref()
+            SEED_PREFIX_STAKE_SUMMARY,
+            user.key().as_ref()
         ],
-        bump = user_stake.bump,
-        constraint = user_stake.user == user.key()
+        bump = user_stake_summary.bump,
+        constraint = user_stake_summary.user == user.key(),
+        constraint = user_stake_summary.is_authorized(authority.key()) @ IdoError::Unauthorized
     )]
-    pub user_stake: Account<'info, UserStake>,
+    pub user_stake_summary: Option<Account<'info, UserStakeSummary>>,
 
     #[account(
         mut,
@@ -540,6 +1789,96 @@ pub struct BuyTokens<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ConfigurePresale<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.creator == creator.key() @ IdoError::Unauthorized,
+        constraint = presale.status == STATUS_PENDING @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureFcfs<'info> {
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.creator == creator.key() @ IdoError::Unauthorized,
+        constraint = presale.status == STATUS_PENDING @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PROJECT,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+        ],
+        bump = project.bump,
+        constraint = project.key() == presale.project
+    )]
+    pub project: Account<'info, Project>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTokensFcfs<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_LIVE @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_INFO,
+            user.key().as_ref(),
+            presale.key().as_ref(),
+        ],
+        bump = user_info.bump,
+        constraint = user_info.user == user.key(),
+        constraint = user_info.presale == presale.key()
+    )]
+    pub user_info: Account<'info, UserPresaleInfo>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct ListToken<'info> {
     #[account(
@@ -553,6 +1892,7 @@ pub struct ListToken<'info> {
             SEED_PREFIX_PRESALE,
             presale.mint_of_token_being_sold.as_ref(),
             presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
         ],
         bump = presale.bump,
         constraint = presale.status == STATUS_COMPLETED @ IdoError::PresaleNotCompleted
@@ -582,3 +1922,136 @@ pub struct ListToken<'info> {
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
+
+#[derive(Accounts)]
+pub struct CollectProtocolFee<'info> {
+    #[account(
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_COMPLETED @ IdoError::PresaleNotCompleted
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        constraint = treasury_wallet.key() == global_state.treasury_wallet @ IdoError::Unauthorized
+    )]
+    /// CHECK: Validated against the treasury wallet in global state
+    pub treasury_wallet: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUserPresaleInfo<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.status == STATUS_COMPLETED || presale.status == STATUS_CANCELLED @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            SEED_PREFIX_USER_INFO,
+            user.key().as_ref(),
+            presale.key().as_ref(),
+        ],
+        bump = user_info.bump,
+        constraint = user_info.user == user.key(),
+        constraint = user_info.presale == presale.key()
+    )]
+    pub user_info: Account<'info, UserPresaleInfo>,
+
+    #[account(
+        mut,
+        constraint = rent_payer.key() == user_info.rent_payer @ IdoError::Unauthorized
+    )]
+    /// CHECK: Validated against the payer recorded on the user info
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePresale<'info> {
+    #[account(
+        mut,
+        constraint = creator.key() == presale.creator @ IdoError::Unauthorized
+    )]
+    pub creator: Signer<'info>,
+
+    #[account(
+        mut,
+        close = creator,
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        mut,
+        close = creator,
+        seeds = [
+            SEED_PREFIX_PRESALE_METADATA,
+            presale.key().as_ref()
+        ],
+        bump = presale_metadata.bump
+    )]
+    pub presale_metadata: Account<'info, PresaleMetadata>,
+
+    // Batch auctions only; required for them so the book's rent is returned
+    #[account(
+        mut,
+        close = creator,
+        seeds = [
+            SEED_PREFIX_ORDER_BOOK,
+            presale.key().as_ref()
+        ],
+        bump = order_book.bump
+    )]
+    pub order_book: Option<Account<'info, BatchOrderBook>>,
+
+    #[account(
+        mut,
+        constraint = presale_token_account.key() == presale.presale_token_account
+    )]
+    pub presale_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == creator.key(),
+        constraint = creator_token_account.mint == presale.mint_of_token_being_sold
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
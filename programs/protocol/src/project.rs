@@ -0,0 +1,225 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::constants::*;
+use crate::errors::*;
+use crate::presale::ConfigurePresale;
+use crate::state::*;
+
+/// Sets up a project the first time one of its instructions runs.
+pub fn init_project(project: &mut Project, mint: Pubkey, creator: Pubkey, bump: u8) {
+    if project.mint == Pubkey::default() {
+        project.mint = mint;
+        project.creator = creator;
+        project.bump = bump;
+        project.version = ACCOUNT_VERSION;
+    }
+}
+
+/// Schedules a new round after the project's latest one and returns the end
+/// of that previous round. Rounds of a project never overlap.
+pub fn schedule_round(
+    project: &mut Project,
+    registration_start_time: i64,
+    end_time: i64,
+) -> Result<i64> {
+    require!(
+        registration_start_time > project.last_round_end_time,
+        IdoError::RoundOverlap
+    );
+
+    let previous_round_end_time = project.last_round_end_time;
+    project.round_count = project.round_count.checked_add(1).unwrap();
+    project.last_round_end_time = end_time;
+
+    Ok(previous_round_end_time)
+}
+
+/// Hands `amount` of the deposited supply to a round. Rounds together can
+/// never be allocated more than the creator deposited.
+pub fn allocate_project_supply(project: &mut Project, amount: u64) -> Result<()> {
+    let allocated = project.tokens_allocated.checked_add(amount).unwrap();
+    require!(
+        allocated <= project.tokens_deposited,
+        IdoError::ProjectSupplyExceeded
+    );
+    project.tokens_allocated = allocated;
+
+    Ok(())
+}
+
+/// Deposits supply that later rounds of the project can be funded from.
+pub fn deposit_project_tokens(ctx: Context<DepositProjectTokens>, amount: u64) -> Result<()> {
+    let project = &mut ctx.accounts.project;
+
+    init_project(
+        project,
+        ctx.accounts.mint.key(),
+        ctx.accounts.creator.key(),
+        ctx.bumps.project,
+    );
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.creator_token_account.to_account_info(),
+        to: ctx.accounts.project_vault.to_account_info(),
+        authority: ctx.accounts.creator.to_account_info(),
+    };
+
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+    project.tokens_deposited = project.tokens_deposited.checked_add(amount).unwrap();
+
+    msg!("Deposited {} tokens into the project", amount);
+
+    Ok(())
+}
+
+/// Replaces the default vesting split and release times for one round.
+/// Release delays are measured from the end of the sale.
+pub fn configure_round_vesting(
+    ctx: Context<ConfigurePresale>,
+    vesting_percentages: [u8; 3],
+    release_delays: [i64; 3],
+) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+
+    require!(presale.vesting_enabled, IdoError::InvalidVestingSchedule);
+
+    let total_percentage: u16 = vesting_percentages.iter().map(|p| *p as u16).sum();
+    require!(total_percentage == 100, IdoError::InvalidVestingSchedule);
+
+    require!(
+        release_delays[0] >= 0
+            && release_delays[0] <= release_delays[1]
+            && release_delays[1] <= release_delays[2],
+        IdoError::InvalidVestingSchedule
+    );
+
+    presale.vesting_percentages = vesting_percentages;
+    presale.first_release_time = presale.end_time.checked_add(release_delays[0]).unwrap();
+    presale.second_release_time = presale.end_time.checked_add(release_delays[1]).unwrap();
+    presale.third_release_time = presale.end_time.checked_add(release_delays[2]).unwrap();
+
+    msg!("Round vesting configured successfully");
+
+    Ok(())
+}
+
+/// Switches a round between tier eligibility and allowlist eligibility.
+pub fn configure_allowlist(ctx: Context<ConfigurePresale>, allowlist_enabled: bool) -> Result<()> {
+    let presale = &mut ctx.accounts.presale;
+
+    presale.allowlist_enabled = allowlist_enabled;
+
+    msg!("Round allowlist enabled: {}", allowlist_enabled);
+
+    Ok(())
+}
+
+/// Grants a wallet a fixed allocation in an approved round. Pending rounds
+/// are excluded since a rejection closes the presale but not its entries.
+pub fn add_to_allowlist(
+    ctx: Context<AddToAllowlist>,
+    user: Pubkey,
+    allocation: u64,
+) -> Result<()> {
+    require!(allocation > 0, IdoError::InsufficientAllocation);
+
+    // The status stays approved until start_presale runs, which may be late
+    require!(
+        Clock::get()?.unix_timestamp < ctx.accounts.presale.start_time,
+        IdoError::InvalidPresaleStatus
+    );
+
+    let entry = &mut ctx.accounts.allowlist_entry;
+    entry.presale = ctx.accounts.presale.key();
+    entry.user = user;
+    entry.allocation = allocation;
+    entry.bump = ctx.bumps.allowlist_entry;
+
+    msg!("Added {} to the allowlist with {} tokens", user, allocation);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct DepositProjectTokens<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        space = 8 + Project::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_PROJECT,
+            mint.key().as_ref(),
+            creator.key().as_ref(),
+        ],
+        bump
+    )]
+    pub project: Account<'info, Project>,
+
+    #[account(
+        init_if_needed,
+        payer = creator,
+        token::mint = mint,
+        token::authority = project,
+        seeds = [
+            SEED_PREFIX_PROJECT,
+            mint.key().as_ref(),
+            creator.key().as_ref(),
+            b"vault"
+        ],
+        bump
+    )]
+    pub project_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = creator_token_account.owner == creator.key(),
+        constraint = creator_token_account.mint == mint.key()
+    )]
+    pub creator_token_account: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        bump = presale.bump,
+        constraint = presale.creator == creator.key() @ IdoError::Unauthorized,
+        // Pending rounds can still be rejected, which would orphan the entries
+        constraint = presale.status == STATUS_APPROVED @ IdoError::InvalidPresaleStatus
+    )]
+    pub presale: Account<'info, Presale>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_ALLOWLIST,
+            presale.key().as_ref(),
+            user.as_ref()
+        ],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,461 @@
+use crate::constants::*;
+use crate::state::*;
+use crate::errors::*;
+use crate::tier::*;
+use crate::utils::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+
+/// Brings the pool's reward-per-share accumulator up to `current_time`.
+/// Emissions stop once the funded rewards run out, so every accrued reward
+/// can be paid from the vault.
+pub fn update_pool_rewards(pool: &mut StakingPool, current_time: i64) {
+    if current_time <= pool.last_update_time {
+        return;
+    }
+
+    if pool.total_staked > 0 {
+        let elapsed = (current_time - pool.last_update_time) as u128;
+        let rewards = elapsed
+            .checked_mul(pool.reward_rate as u128)
+            .unwrap()
+            .min(pool.rewards_remaining as u128);
+        pool.rewards_remaining -= rewards as u64;
+
+        pool.acc_reward_per_share = pool
+            .acc_reward_per_share
+            .checked_add(
+                rewards
+                    .checked_mul(REWARD_PRECISION)
+                    .unwrap()
+                    .checked_div(pool.total_staked as u128)
+                    .unwrap(),
+            )
+            .unwrap();
+    }
+
+    // Fee revenue streams until the end of the current stream
+    let sol_end_time = current_time.min(pool.sol_reward_end_time);
+    if sol_end_time > pool.last_update_time {
+        let elapsed = sol_end_time - pool.last_update_time;
+
+        if pool.total_staked > 0 {
+            pool.acc_sol_per_share = pool
+                .acc_sol_per_share
+                .checked_add(
+                    (elapsed as u128)
+                        .checked_mul(pool.sol_reward_rate)
+                        .unwrap()
+                        .checked_div(pool.total_staked as u128)
+                        .unwrap(),
+                )
+                .unwrap();
+        } else {
+            // Nobody to pay: the stream pauses and carries over to the
+            // next staker instead of stranding lamports in the pool
+            pool.sol_reward_end_time = pool.sol_reward_end_time.checked_add(elapsed).unwrap();
+        }
+    }
+
+    pool.last_update_time = current_time;
+}
+
+/// Streams protocol fee revenue already deposited into the pool account to
+/// stakers over `SOL_REWARD_DURATION`, together with whatever is left of the
+/// current stream. Paying it out at once would let a stake opened just
+/// before collection take a full share. The pool must be updated to
+/// `current_time` first.
+pub fn distribute_sol_rewards(pool: &mut StakingPool, lamports: u64, current_time: i64) {
+    let remaining = if current_time < pool.sol_reward_end_time {
+        ((pool.sol_reward_end_time - current_time) as u128)
+            .checked_mul(pool.sol_reward_rate)
+            .unwrap()
+    } else {
+        0
+    };
+
+    pool.sol_reward_rate = (lamports as u128)
+        .checked_mul(REWARD_PRECISION)
+        .unwrap()
+        .checked_add(remaining)
+        .unwrap()
+        .checked_div(SOL_REWARD_DURATION as u128)
+        .unwrap();
+    pool.sol_reward_end_time = current_time.checked_add(SOL_REWARD_DURATION).unwrap();
+}
+
+/// Weighted power of everything staked in a pool, in native token units.
+/// Fee revenue is split between pools by this value.
+pub fn get_pool_power(pool: &StakingPool, config: &StakingMintConfig) -> u64 {
+    let power_amount = if config.is_lp_token {
+        get_lp_underlying_amount(pool.total_staked, config.lp_rate)
+    } else {
+        normalize_to_native_decimals(pool.total_staked, config.decimals, config.native_decimals)
+    };
+    let weight_bps = if config.enabled { config.weight_bps } else { 0 };
+
+    (power_amount as u128)
+        .checked_mul(weight_bps as u128)
+        .unwrap()
+        .checked_div(BPS_DENOMINATOR as u128)
+        .unwrap() as u64
+}
+
+/// Moves rewards and fee revenue earned at the current stake amount into
+/// the pending balances. Must run before `user_stake.amount` changes.
+pub fn settle_user_rewards(pool: &StakingPool, user_stake: &mut UserStake) {
+    let accrued = (user_stake.amount as u128)
+        .checked_mul(pool.acc_reward_per_share)
+        .unwrap()
+        / REWARD_PRECISION;
+
+    let pending = accrued.saturating_sub(user_stake.reward_debt) as u64;
+    user_stake.pending_rewards = user_stake.pending_rewards.checked_add(pending).unwrap();
+
+    let accrued_sol = (user_stake.amount as u128)
+        .checked_mul(pool.acc_sol_per_share)
+        .unwrap()
+        / REWARD_PRECISION;
+
+    let pending_sol = accrued_sol.saturating_sub(user_stake.sol_reward_debt) as u64;
+    user_stake.pending_sol_rewards = user_stake
+        .pending_sol_rewards
+        .checked_add(pending_sol)
+        .unwrap();
+}
+
+/// Resets the reward debts after `user_stake.amount` changed.
+pub fn reset_reward_debt(pool: &StakingPool, user_stake: &mut UserStake) {
+    user_stake.reward_debt = (user_stake.amount as u128)
+        .checked_mul(pool.acc_reward_per_share)
+        .unwrap()
+        / REWARD_PRECISION;
+
+    user_stake.sol_reward_debt = (user_stake.amount as u128)
+        .checked_mul(pool.acc_sol_per_share)
+        .unwrap()
+        / REWARD_PRECISION;
+}
+
+pub fn initialize_staking_pool(
+    ctx: Context<InitializeStakingPool>,
+    reward_rate: u64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.staking_pool;
+
+    pool.staking_token_mint = ctx.accounts.staking_token_mint.key();
+    pool.reward_mint = ctx.accounts.reward_mint.key();
+    pool.reward_vault = ctx.accounts.reward_vault.key();
+    pool.total_staked = 0;
+    pool.reward_rate = reward_rate;
+    pool.acc_reward_per_share = 0;
+    pool.last_update_time = Clock::get()?.unix_timestamp;
+    pool.acc_sol_per_share = 0;
+    pool.sol_reward_rate = 0;
+    pool.sol_reward_end_time = 0;
+    pool.rewards_remaining = 0;
+    pool.bump = ctx.bumps.staking_pool;
+
+    let global_state = &mut ctx.accounts.global_state;
+    global_state.staking_pool_count = global_state.staking_pool_count.checked_add(1).unwrap();
+
+    msg!("Staking pool initialized with reward rate {}", reward_rate);
+
+    Ok(())
+}
+
+pub fn fund_reward_vault(ctx: Context<FundRewardVault>, amount: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.staking_pool;
+
+    // Time spent unfunded emits nothing, so it mustn't draw on the new funds
+    update_pool_rewards(pool, Clock::get()?.unix_timestamp);
+    pool.rewards_remaining = pool.rewards_remaining.checked_add(amount).unwrap();
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.admin_token_account.to_account_info(),
+        to: ctx.accounts.reward_vault.to_account_info(),
+        authority: ctx.accounts.admin.to_account_info(),
+    };
+
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+    token::transfer(cpi_ctx, amount)?;
+
+    msg!("Reward vault funded with {} tokens", amount);
+
+    Ok(())
+}
+
+pub fn set_reward_rate(ctx: Context<SetRewardRate>, reward_rate: u64) -> Result<()> {
+    let pool = &mut ctx.accounts.staking_pool;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    // Accrue at the old rate up to now before switching
+    update_pool_rewards(pool, current_time);
+    pool.reward_rate = reward_rate;
+
+    msg!("Reward rate updated to {}", reward_rate);
+
+    Ok(())
+}
+
+pub fn claim_staking_rewards(ctx: Context<ClaimStakingRewards>) -> Result<()> {
+    let pool = &mut ctx.accounts.staking_pool;
+    let user_stake = &mut ctx.accounts.user_stake;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    update_pool_rewards(pool, current_time);
+    settle_user_rewards(pool, user_stake);
+    reset_reward_debt(pool, user_stake);
+
+    let amount_to_claim = user_stake.pending_rewards;
+
+    require!(amount_to_claim > 0, IdoError::NothingToClaim);
+
+    user_stake.pending_rewards = 0;
+
+    // Transfer rewards from the vault, signed by the pool
+    let seeds = &[
+        SEED_PREFIX_STAKING_POOL,
+        pool.staking_token_mint.as_ref(),
+        &[pool.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.reward_vault.to_account_info(),
+        to: ctx.accounts.user_reward_token_account.to_account_info(),
+        authority: pool.to_account_info(),
+    };
+
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+
+    token::transfer(cpi_ctx, amount_to_claim)?;
+
+    msg!("User claimed {} staking reward tokens", amount_to_claim);
+
+    Ok(())
+}
+
+pub fn claim_staker_revenue(ctx: Context<ClaimStakerRevenue>) -> Result<()> {
+    let pool = &mut ctx.accounts.staking_pool;
+    let user_stake = &mut ctx.accounts.user_stake;
+    let current_time = Clock::get()?.unix_timestamp;
+
+    update_pool_rewards(pool, current_time);
+    settle_user_rewards(pool, user_stake);
+    reset_reward_debt(pool, user_stake);
+
+    let amount_to_claim = user_stake.pending_sol_rewards;
+
+    require!(amount_to_claim > 0, IdoError::NothingToClaim);
+
+    user_stake.pending_sol_rewards = 0;
+
+    // Fee revenue is held as lamports on the pool account itself
+    transfer_lamports_from_pda(
+        &pool.to_account_info(),
+        &ctx.accounts.user.to_account_info(),
+        amount_to_claim,
+    )?;
+
+    msg!("User claimed {} lamports of protocol fee revenue", amount_to_claim);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakingPool<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + StakingPool::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_token_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = reward_mint,
+        token::authority = staking_pool,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_token_mint.key().as_ref(),
+            b"reward_vault"
+        ],
+        bump
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    pub staking_token_mint: Account<'info, Mint>,
+
+    #[account(
+        seeds = [
+            SEED_PREFIX_STAKING_MINT,
+            staking_token_mint.key().as_ref()
+        ],
+        bump = staking_mint_config.bump
+    )]
+    pub staking_mint_config: Account<'info, StakingMintConfig>,
+
+    pub reward_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundRewardVault<'info> {
+    #[account(
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_pool.staking_token_mint.as_ref()
+        ],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+
+    #[account(
+        mut,
+        constraint = admin_token_account.owner == admin.key(),
+        constraint = admin_token_account.mint == staking_pool.reward_mint
+    )]
+    pub admin_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = reward_vault.key() == staking_pool.reward_vault
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardRate<'info> {
+    #[account(
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_pool.staking_token_mint.as_ref()
+        ],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimStakingRewards<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_STAKE,
+            user.key().as_ref(),
+            staking_pool.staking_token_mint.as_ref(),
+            user_stake.index_seed().as_ref()
+        ],
+        bump = user_stake.bump,
+        constraint = user_stake.user == user.key()
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_pool.staking_token_mint.as_ref()
+        ],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+
+    #[account(
+        mut,
+        constraint = reward_vault.key() == staking_pool.reward_vault
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = user_reward_token_account.owner == user.key(),
+        constraint = user_reward_token_account.mint == staking_pool.reward_mint
+    )]
+    pub user_reward_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimStakerRevenue<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_STAKE,
+            user.key().as_ref(),
+            staking_pool.staking_token_mint.as_ref(),
+            user_stake.index_seed().as_ref()
+        ],
+        bump = user_stake.bump,
+        constraint = user_stake.user == user.key()
+    )]
+    pub user_stake: Account<'info, UserStake>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_pool.staking_token_mint.as_ref()
+        ],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+}
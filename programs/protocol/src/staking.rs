@@ -1,18 +1,179 @@
 use crate::constants::*;
 use crate::state::*;
 use crate::errors::*;
+use crate::rewards::*;
 use crate::tier::*;
+use crate::utils::*;
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer, Mint};
 
 
+/// Amount that counts towards tier power, in native token units: LP
+/// positions are valued at the mint's rate-limited LP rate, never the
+/// reserve's spot balance, and other mints are scaled by their decimals.
+pub fn get_position_power_amount(user_stake: &UserStake, config: &StakingMintConfig) -> u64 {
+    if !config.is_lp_token {
+        return normalize_to_native_decimals(
+            user_stake.amount,
+            config.decimals,
+            config.native_decimals,
+        );
+    }
+    
+    get_lp_underlying_amount(user_stake.amount, config.lp_rate)
+}
+
+/// Re-snapshots a position's weighted amount and folds the difference into
+/// the user's summary. `previous_power` is the position's weighted power
+/// before the change.
+pub fn apply_position_to_summary(
+    summary: &mut UserStakeSummary,
+    user_stake: &mut UserStake,
+    previous_power: u64,
+    current_time: i64,
+) {
+    summary.total_power = summary
+        .total_power
+        .saturating_sub(previous_power)
+        .checked_add(get_weighted_power(user_stake))
+        .unwrap();
+    
+    let effective_amount = get_effective_stake(user_stake, current_time);
+    
+    summary.effective_amount = summary
+        .effective_amount
+        .saturating_sub(user_stake.effective_amount)
+        .checked_add(effective_amount)
+        .unwrap();
+    user_stake.effective_amount = effective_amount;
+    
+    // The summary overstates the tier once a lock boost runs out
+    if current_time < user_stake.lock_end
+        && user_stake.lock_weight_bps > LOCK_TERM_FLEXIBLE_WEIGHT_BPS
+    {
+        summary.boost_expiry = summary.boost_expiry.min(user_stake.lock_end);
+    }
+    
+    summary.tier = get_tier_for_amount(summary.effective_amount);
+}
+
+/// Registers a new position on the summary. Positions are opened one after
+/// another; indices of closed positions can be reused.
+pub fn open_position(
+    summary: &mut UserStakeSummary,
+    user_stake: &UserStake,
+    owner: Pubkey,
+    position_index: u32,
+    summary_bump: u8,
+) -> Result<()> {
+    require!(
+        position_index <= summary.next_position_index,
+        IdoError::InvalidPositionIndex
+    );
+    
+    if summary.user == Pubkey::default() {
+        summary.user = owner;
+        summary.boost_expiry = i64::MAX;
+        summary.bump = summary_bump;
+    }
+    
+    // A freshly created account, either a new index or a closed one reopened
+    if user_stake.user == Pubkey::default() {
+        require!(
+            summary.position_count < MAX_STAKE_POSITIONS,
+            IdoError::TooManyStakePositions
+        );
+    
+        summary.position_count = summary.position_count.checked_add(1).unwrap();
+        
+        if position_index == summary.next_position_index {
+            summary.next_position_index = summary.next_position_index.checked_add(1).unwrap();
+        }
+    }
+    
+    Ok(())
+}
+
+/// Adds `amount` to a position and applies the chosen lock term.
+pub fn add_to_position(
+    user_stake: &mut UserStake,
+    amount: u64,
+    lock_term: u8,
+    current_time: i64,
+) -> Result<()> {
+    let (lock_duration, lock_weight_bps) = get_lock_term(lock_term)?;
+    let new_amount = user_stake.amount.checked_add(amount).unwrap();
+    
+    // Top-ups average into the lock time so they don't wipe out loyalty
+    user_stake.lock_time = if user_stake.amount == 0 {
+        current_time
+    } else {
+        ((user_stake.amount as i128 * user_stake.lock_time as i128
+            + amount as i128 * current_time as i128)
+            / new_amount as i128) as i64
+    };
+    user_stake.amount = new_amount;
+    
+    // A new lock can extend the existing one but never shorten it
+    if lock_duration > 0 {
+        let lock_end = current_time.checked_add(lock_duration).unwrap();
+        
+        if current_time >= user_stake.lock_end {
+            user_stake.lock_weight_bps = lock_weight_bps;
+        } else {
+            user_stake.lock_weight_bps = user_stake.lock_weight_bps.max(lock_weight_bps);
+        }
+        user_stake.lock_end = user_stake.lock_end.max(lock_end);
+    } else if current_time >= user_stake.lock_end {
+        user_stake.lock_weight_bps = LOCK_TERM_FLEXIBLE_WEIGHT_BPS;
+    }
+    
+    Ok(())
+}
+
+/// Checks that `amount` can be withdrawn from the position right now.
+pub fn check_unstake(user_stake: &UserStake, amount: u64, current_time: i64) -> Result<()> {
+    // Ensure user has enough staked
+    require!(
+        user_stake.amount >= amount,
+        IdoError::InsufficientAllocation
+    );
+    
+    // Fixed-term positions can't be withdrawn before the lock expires
+    require!(current_time >= user_stake.lock_end, IdoError::StakeLocked);
+    
+    Ok(())
+}
+
 pub fn stake_tokens(
     ctx: Context<StakeTokens>,
+    position_index: u32,
     amount: u64,
+    lock_term: u8,
 ) -> Result<()> {
-    // let global_state = &ctx.accounts.global_state;
     let user_stake = &mut ctx.accounts.user_stake;
+    let summary = &mut ctx.accounts.user_stake_summary;
+    let staking_pool = &mut ctx.accounts.staking_pool;
     let current_time = Clock::get()?.unix_timestamp;
+    let (previous_total, previous_tier) = (summary.total_power, summary.tier);
+    
+    open_position(
+        summary,
+        user_stake,
+        ctx.accounts.user.key(),
+        position_index,
+        ctx.bumps.user_stake_summary,
+    )?;
+    
+    if user_stake.user == Pubkey::default() {
+        user_stake.rent_payer = ctx.accounts.user.key();
+        user_stake.version = ACCOUNT_VERSION;
+    }
+
+    // Settle rewards earned on the existing stake before it grows
+    update_pool_rewards(staking_pool, current_time);
+    settle_user_rewards(staking_pool, user_stake);
+    let previous_power = get_weighted_power(user_stake);
     
     // Transfer tokens from user to stake account
     let cpi_accounts = Transfer {
@@ -29,12 +190,106 @@ pub fn stake_tokens(
     // Update user stake info
     user_stake.user = ctx.accounts.user.key();
     user_stake.staking_token_mint = ctx.accounts.staking_token_mint.key();
-    user_stake.amount = amount;
-    user_stake.lock_time = current_time;
-    user_stake.tier = get_tier_for_amount(amount);
+    user_stake.position_index = position_index;
+    user_stake.mint_weight_bps = ctx.accounts.staking_mint_config.weight_bps;
+    add_to_position(user_stake, amount, lock_term, current_time)?;
+    user_stake.power_amount = get_position_power_amount(user_stake, &ctx.accounts.staking_mint_config);
+    user_stake.bump = ctx.bumps.user_stake;
+    
+    // Update the user's aggregate stake
+    apply_position_to_summary(summary, user_stake, previous_power, current_time);
+    record_stake_change(&mut ctx.accounts.global_state, previous_total, previous_tier, summary);
+
+    staking_pool.total_staked = staking_pool.total_staked.checked_add(amount).unwrap();
+    reset_reward_debt(staking_pool, user_stake);
+    
+    msg!("User has staked {} tokens and qualified for tier {}", amount, summary.tier);
+    
+    Ok(())
+}
+
+/// Stakes the payer's tokens into a new position owned by `beneficiary`. The
+/// beneficiary or their delegate must sign, and existing positions can't be
+/// topped up this way since that would extend their lock. Only the
+/// beneficiary can unstake.
+pub fn stake_for(
+    ctx: Context<StakeFor>,
+    position_index: u32,
+    amount: u64,
+    lock_term: u8,
+) -> Result<()> {
+    let beneficiary = ctx.accounts.beneficiary.key();
+    let user_stake = &mut ctx.accounts.user_stake;
+    let summary = &mut ctx.accounts.user_stake_summary;
+    let staking_pool = &mut ctx.accounts.staking_pool;
+    let current_time = Clock::get()?.unix_timestamp;
+    let (previous_total, previous_tier) = (summary.total_power, summary.tier);
+    
+    require!(amount > 0, IdoError::InsufficientAllocation);
+    require!(user_stake.amount == 0, IdoError::StakePositionNotEmpty);
+    
+    open_position(
+        summary,
+        user_stake,
+        beneficiary,
+        position_index,
+        ctx.bumps.user_stake_summary,
+    )?;
+    
+    // The payer funded the account rent, so the refund goes back to them
+    if user_stake.user == Pubkey::default() {
+        user_stake.rent_payer = ctx.accounts.payer.key();
+        user_stake.version = ACCOUNT_VERSION;
+    }
+    
+    // Settle rewards earned on the existing stake before it grows
+    update_pool_rewards(staking_pool, current_time);
+    settle_user_rewards(staking_pool, user_stake);
+    let previous_power = get_weighted_power(user_stake);
+    
+    // Transfer tokens from payer to the beneficiary's stake account
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.payer_token_account.to_account_info(),
+        to: ctx.accounts.stake_token_account.to_account_info(),
+        authority: ctx.accounts.payer.to_account_info(),
+    };
+    
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    
+    token::transfer(cpi_ctx, amount)?;
+    
+    // Update beneficiary stake info
+    user_stake.user = beneficiary;
+    user_stake.staking_token_mint = ctx.accounts.staking_token_mint.key();
+    user_stake.position_index = position_index;
+    user_stake.mint_weight_bps = ctx.accounts.staking_mint_config.weight_bps;
+    add_to_position(user_stake, amount, lock_term, current_time)?;
+    user_stake.power_amount = get_position_power_amount(user_stake, &ctx.accounts.staking_mint_config);
     user_stake.bump = ctx.bumps.user_stake;
     
-    msg!("User has staked {} tokens and qualified for tier {}", amount, user_stake.tier);
+    // Update the beneficiary's aggregate stake
+    apply_position_to_summary(summary, user_stake, previous_power, current_time);
+    record_stake_change(&mut ctx.accounts.global_state, previous_total, previous_tier, summary);
+    
+    staking_pool.total_staked = staking_pool.total_staked.checked_add(amount).unwrap();
+    reset_reward_debt(staking_pool, user_stake);
+    
+    msg!("Staked {} tokens on behalf of {}", amount, beneficiary);
+    
+    Ok(())
+}
+
+pub fn set_stake_delegate(
+    ctx: Context<SetStakeDelegate>,
+    delegate: Option<Pubkey>,
+) -> Result<()> {
+    let summary = &mut ctx.accounts.user_stake_summary;
+    
+    // Delegates can register and buy for the owner but never unstake
+    summary.delegate = delegate.unwrap_or_default();
+    
+    msg!("Stake delegate updated");
     
     Ok(())
 }
@@ -46,20 +301,17 @@ pub fn unstake_tokens(
     // Store references to data we need before mutable borrow
     let user_pubkey = ctx.accounts.user_stake.user;
     let token_mint = ctx.accounts.user_stake.staking_token_mint;
+    let position_index = ctx.accounts.user_stake.index_seed();
     let bump = ctx.accounts.user_stake.bump;
-    let staked_amount = ctx.accounts.user_stake.amount;
     
-    // Ensure user has enough staked
-    require!(
-        staked_amount >= amount,
-        IdoError::InsufficientAllocation
-    );
+    check_unstake(&ctx.accounts.user_stake, amount, Clock::get()?.unix_timestamp)?;
     
     // Transfer tokens from stake account back to user
     let seeds = &[
         SEED_PREFIX_USER_STAKE,
         user_pubkey.as_ref(),
         token_mint.as_ref(),
+        position_index.as_ref(),
         &[bump],
     ];
     let signer = &[&seeds[..]];
@@ -77,34 +329,255 @@ pub fn unstake_tokens(
     
     // Now we can safely get a mutable reference
     let user_stake = &mut ctx.accounts.user_stake;
+    let summary = &mut ctx.accounts.user_stake_summary;
+    let staking_pool = &mut ctx.accounts.staking_pool;
+    let current_time = Clock::get()?.unix_timestamp;
+    let (previous_total, previous_tier) = (summary.total_power, summary.tier);
+    
+    // Settle rewards earned on the existing stake before it shrinks
+    update_pool_rewards(staking_pool, current_time);
+    settle_user_rewards(staking_pool, user_stake);
+    let previous_power = get_weighted_power(user_stake);
     
     // Update user stake info
     user_stake.amount = user_stake.amount.saturating_sub(amount);
-    user_stake.tier = get_tier_for_amount(user_stake.amount);
+    user_stake.mint_weight_bps = ctx.accounts.staking_mint_config.weight_bps;
+    user_stake.power_amount = get_position_power_amount(user_stake, &ctx.accounts.staking_mint_config);
     
-    msg!("User has unstaked {} tokens and is now in tier {}", amount, user_stake.tier);
+    apply_position_to_summary(summary, user_stake, previous_power, current_time);
+    record_stake_change(&mut ctx.accounts.global_state, previous_total, previous_tier, summary);
+    
+    staking_pool.total_staked = staking_pool.total_staked.saturating_sub(amount);
+    reset_reward_debt(staking_pool, user_stake);
+    
+    msg!("User has unstaked {} tokens and is now in tier {}", amount, summary.tier);
+    
+    Ok(())
+}
+
+/// Closes an emptied position and its token account. Rewards must be claimed
+/// first; rent goes back to whoever opened the position.
+pub fn close_user_stake(ctx: Context<CloseUserStake>) -> Result<()> {
+    let user_stake = &ctx.accounts.user_stake;
+    
+    require!(
+        user_stake.amount == 0
+            && user_stake.pending_rewards == 0
+            && user_stake.pending_sol_rewards == 0
+            && ctx.accounts.stake_token_account.amount == 0,
+        IdoError::StakePositionNotEmpty
+    );
+    
+    let position_index = user_stake.index_seed();
+    let seeds = &[
+        SEED_PREFIX_USER_STAKE,
+        user_stake.user.as_ref(),
+        user_stake.staking_token_mint.as_ref(),
+        position_index.as_ref(),
+        &[user_stake.bump],
+    ];
+    let signer = &[&seeds[..]];
+    
+    let cpi_accounts = CloseAccount {
+        account: ctx.accounts.stake_token_account.to_account_info(),
+        destination: ctx.accounts.rent_payer.to_account_info(),
+        authority: user_stake.to_account_info(),
+    };
+    
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    token::close_account(CpiContext::new_with_signer(cpi_program, cpi_accounts, signer))?;
+    
+    let summary = &mut ctx.accounts.user_stake_summary;
+    summary.position_count = summary.position_count.saturating_sub(1);
+    
+    msg!("Stake position {} closed", user_stake.position_index);
+    
+    Ok(())
+}
+
+/// Recomputes the summary from scratch. Permissionless; every open position
+/// of the user must be passed (writable) in `remaining_accounts`, each
+/// followed by its mint's config. Positions are revalued at the current mint
+/// weight and LP rate; disabled mints stop counting.
+pub fn refresh_stake_summary(ctx: Context<RefreshStakeSummary>) -> Result<()> {
+    let summary = &mut ctx.accounts.user_stake_summary;
+    let current_time = Clock::get()?.unix_timestamp;
+    let (previous_total, previous_tier) = (summary.total_power, summary.tier);
+    
+    require!(
+        ctx.remaining_accounts.len() as u64 == summary.position_count as u64 * 2,
+        IdoError::InvalidStakePositions
+    );
+    
+    let mut position_keys = Vec::with_capacity(summary.position_count as usize);
+    
+    summary.total_power = 0;
+    summary.effective_amount = 0;
+    summary.boost_expiry = i64::MAX;
+    
+    for accounts in ctx.remaining_accounts.chunks(2) {
+        let (position_info, config_info) = (&accounts[0], &accounts[1]);
+        
+        require!(
+            position_info.owner == &crate::ID
+                && position_info.is_writable
+                && config_info.owner == &crate::ID,
+            IdoError::InvalidStakePositions
+        );
+        
+        let mut user_stake = {
+            let data = position_info.try_borrow_data()?;
+            UserStake::try_deserialize(&mut &data[..])?
+        };
+        let config = {
+            let data = config_info.try_borrow_data()?;
+            StakingMintConfig::try_deserialize(&mut &data[..])?
+        };
+        
+        require!(
+            user_stake.user == summary.user && config.mint == user_stake.staking_token_mint,
+            IdoError::InvalidStakePositions
+        );
+        
+        user_stake.mint_weight_bps = if config.enabled { config.weight_bps } else { 0 };
+        user_stake.power_amount = get_position_power_amount(&user_stake, &config);
+        
+        // Start from zero so the position's full weight is counted
+        user_stake.effective_amount = 0;
+        apply_position_to_summary(summary, &mut user_stake, 0, current_time);
+        
+        let mut data = position_info.try_borrow_mut_data()?;
+        user_stake.try_serialize(&mut &mut data[..])?;
+        
+        position_keys.push(position_info.key());
+    }
+    
+    // Reject duplicates so a position can't be counted twice
+    position_keys.sort();
+    position_keys.dedup();
+    require!(
+        position_keys.len() as u64 == summary.position_count as u64,
+        IdoError::InvalidStakePositions
+    );
+    
+    summary.tier = get_tier_for_amount(summary.effective_amount);
+    record_stake_change(&mut ctx.accounts.global_state, previous_total, previous_tier, summary);
+    
+    msg!("Stake summary refreshed, user is in tier {}", summary.tier);
+    
+    Ok(())
+}
+
+pub fn add_staking_mint(ctx: Context<AddStakingMint>, weight_bps: u16) -> Result<()> {
+    let config = &mut ctx.accounts.staking_mint_config;
+    
+    config.mint = ctx.accounts.staking_token_mint.key();
+    config.weight_bps = weight_bps;
+    config.enabled = true;
+    config.decimals = ctx.accounts.staking_token_mint.decimals;
+    config.native_decimals = ctx.accounts.native_token_mint.decimals;
+    
+    // LP mints are valued through the pool's native token reserve, starting
+    // from the spot rate when the admin adds them
+    match &ctx.accounts.lp_reserve_account {
+        Some(reserve) => {
+            config.is_lp_token = true;
+            config.lp_reserve_account = reserve.key();
+            config.lp_rate =
+                get_lp_spot_rate(reserve.amount, ctx.accounts.staking_token_mint.supply);
+            config.lp_rate_updated_at = Clock::get()?.unix_timestamp;
+        }
+        None => {
+            config.is_lp_token = false;
+            config.lp_reserve_account = Pubkey::default();
+            config.lp_rate = 0;
+            config.lp_rate_updated_at = 0;
+        }
+    }
+    config.bump = ctx.bumps.staking_mint_config;
+    
+    msg!("Staking mint added with weight {} bps", weight_bps);
+    
+    Ok(())
+}
+
+pub fn update_staking_mint(
+    ctx: Context<UpdateStakingMint>,
+    weight_bps: u16,
+    enabled: bool,
+) -> Result<()> {
+    let config = &mut ctx.accounts.staking_mint_config;
+    
+    config.weight_bps = weight_bps;
+    config.enabled = enabled;
+    
+    msg!("Staking mint updated: weight {} bps, enabled {}", weight_bps, enabled);
+    
+    Ok(())
+}
+
+/// Permissionless crank moving an LP mint's rate one bounded step towards
+/// the pool's spot rate, at most once per `LP_RATE_UPDATE_INTERVAL`.
+/// Positions pick the new rate up on their next stake, unstake or refresh.
+pub fn update_lp_rate(ctx: Context<UpdateLpRate>) -> Result<()> {
+    let config = &mut ctx.accounts.staking_mint_config;
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    require!(
+        current_time >= config.lp_rate_updated_at.checked_add(LP_RATE_UPDATE_INTERVAL).unwrap(),
+        IdoError::LpRateUpdateTooEarly
+    );
+    
+    let spot_rate = get_lp_spot_rate(
+        ctx.accounts.lp_reserve_account.amount,
+        ctx.accounts.staking_token_mint.supply,
+    );
+    
+    // A zero rate has nothing to bound against
+    config.lp_rate = if config.lp_rate == 0 {
+        spot_rate
+    } else {
+        get_bounded_lp_rate(config.lp_rate, spot_rate)
+    };
+    config.lp_rate_updated_at = current_time;
+    
+    msg!("LP rate updated to {}", config.lp_rate);
     
     Ok(())
 }
 
 #[derive(Accounts)]
+#[instruction(position_index: u32)]
 pub struct StakeTokens<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     
     #[account(
-        init,
+        init_if_needed,
         payer = user,
-        space = 8 + std::mem::size_of::<UserStake>(),
+        space = 8 + UserStake::INIT_SPACE,
         seeds = [
             SEED_PREFIX_USER_STAKE,
             user.key().as_ref(),
-            staking_token_mint.key().as_ref()
+            staking_token_mint.key().as_ref(),
+            position_index.to_le_bytes().as_ref()
         ],
         bump
     )]
     pub user_stake: Account<'info, UserStake>,
     
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserStakeSummary::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_STAKE_SUMMARY,
+            user.key().as_ref()
+        ],
+        bump
+    )]
+    pub user_stake_summary: Account<'info, UserStakeSummary>,
+    
     #[account(
         mut,
         constraint = user_token_account.owner == user.key(),
@@ -113,7 +586,7 @@ pub struct StakeTokens<'info> {
     pub user_token_account: Account<'info, TokenAccount>,
     
     #[account(
-        init,
+        init_if_needed,
         payer = user,
         token::mint = staking_token_mint,
         token::authority = user_stake,
@@ -121,18 +594,136 @@ pub struct StakeTokens<'info> {
             SEED_PREFIX_USER_STAKE,
             user.key().as_ref(),
             staking_token_mint.key().as_ref(),
+            position_index.to_le_bytes().as_ref(),
             b"token_account"
         ],
         bump
     )]
     pub stake_token_account: Account<'info, TokenAccount>,
     
+    pub staking_token_mint: Account<'info, Mint>,
+    
+    #[account(
+        seeds = [
+            SEED_PREFIX_STAKING_MINT,
+            staking_token_mint.key().as_ref()
+        ],
+        bump = staking_mint_config.bump,
+        constraint = staking_mint_config.enabled @ IdoError::StakingMintNotAccepted
+    )]
+    pub staking_mint_config: Account<'info, StakingMintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_token_mint.key().as_ref()
+        ],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(position_index: u32)]
+pub struct StakeFor<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    // The beneficiary or the delegate they set on their summary
+    #[account(
+        constraint = authority.key() == beneficiary.key()
+            || authority.key() == user_stake_summary.delegate @ IdoError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Wallet that will own the stake; only used as a PDA seed
+    pub beneficiary: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserStake::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_USER_STAKE,
+            beneficiary.key().as_ref(),
+            staking_token_mint.key().as_ref(),
+            position_index.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UserStakeSummary::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_STAKE_SUMMARY,
+            beneficiary.key().as_ref()
+        ],
+        bump
+    )]
+    pub user_stake_summary: Account<'info, UserStakeSummary>,
+    
+    #[account(
+        mut,
+        constraint = payer_token_account.owner == payer.key(),
+        constraint = payer_token_account.mint == staking_token_mint.key()
+    )]
+    pub payer_token_account: Account<'info, TokenAccount>,
+    
     #[account(
-        constraint = staking_token_mint.key() == global_state.staking_token_mint
+        init_if_needed,
+        payer = payer,
+        token::mint = staking_token_mint,
+        token::authority = user_stake,
+        seeds = [
+            SEED_PREFIX_USER_STAKE,
+            beneficiary.key().as_ref(),
+            staking_token_mint.key().as_ref(),
+            position_index.to_le_bytes().as_ref(),
+            b"token_account"
+        ],
+        bump
     )]
+    pub stake_token_account: Account<'info, TokenAccount>,
+    
     pub staking_token_mint: Account<'info, Mint>,
     
     #[account(
+        seeds = [
+            SEED_PREFIX_STAKING_MINT,
+            staking_token_mint.key().as_ref()
+        ],
+        bump = staking_mint_config.bump,
+        constraint = staking_mint_config.enabled @ IdoError::StakingMintNotAccepted
+    )]
+    pub staking_mint_config: Account<'info, StakingMintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_token_mint.key().as_ref()
+        ],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(
+        mut,
         seeds = [b"global_state"],
         bump = global_state.bump
     )]
@@ -143,6 +734,22 @@ pub struct StakeTokens<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct SetStakeDelegate<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKE_SUMMARY,
+            user.key().as_ref()
+        ],
+        bump = user_stake_summary.bump,
+        constraint = user_stake_summary.user == user.key()
+    )]
+    pub user_stake_summary: Account<'info, UserStakeSummary>,
+}
+
 #[derive(Accounts)]
 pub struct UnstakeTokens<'info> {
     #[account(mut)]
@@ -153,13 +760,24 @@ pub struct UnstakeTokens<'info> {
         seeds = [
             SEED_PREFIX_USER_STAKE,
             user.key().as_ref(),
-            staking_token_mint.key().as_ref()
+            staking_token_mint.key().as_ref(),
+            user_stake.index_seed().as_ref()
         ],
         bump = user_stake.bump,
         constraint = user_stake.user == user.key()
     )]
     pub user_stake: Account<'info, UserStake>,
     
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKE_SUMMARY,
+            user.key().as_ref()
+        ],
+        bump = user_stake_summary.bump
+    )]
+    pub user_stake_summary: Account<'info, UserStakeSummary>,
+    
     #[account(
         mut,
         constraint = user_token_account.owner == user.key(),
@@ -173,6 +791,7 @@ pub struct UnstakeTokens<'info> {
             SEED_PREFIX_USER_STAKE,
             user.key().as_ref(),
             staking_token_mint.key().as_ref(),
+            user_stake.index_seed().as_ref(),
             b"token_account"
         ],
         bump
@@ -181,5 +800,189 @@ pub struct UnstakeTokens<'info> {
     
     pub staking_token_mint: Account<'info, Mint>,
     
+    // Disabled mints can still be unstaked
+    #[account(
+        seeds = [
+            SEED_PREFIX_STAKING_MINT,
+            staking_token_mint.key().as_ref()
+        ],
+        bump = staking_mint_config.bump
+    )]
+    pub staking_mint_config: Account<'info, StakingMintConfig>,
+    
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_POOL,
+            staking_token_mint.key().as_ref()
+        ],
+        bump = staking_pool.bump
+    )]
+    pub staking_pool: Account<'info, StakingPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    
     pub token_program: Program<'info, Token>,
-}
\ No newline at end of file
+}
+
+#[derive(Accounts)]
+pub struct CloseUserStake<'info> {
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            SEED_PREFIX_USER_STAKE,
+            user.key().as_ref(),
+            user_stake.staking_token_mint.as_ref(),
+            user_stake.index_seed().as_ref()
+        ],
+        bump = user_stake.bump,
+        constraint = user_stake.user == user.key()
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_USER_STAKE,
+            user.key().as_ref(),
+            user_stake.staking_token_mint.as_ref(),
+            user_stake.index_seed().as_ref(),
+            b"token_account"
+        ],
+        bump
+    )]
+    pub stake_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKE_SUMMARY,
+            user.key().as_ref()
+        ],
+        bump = user_stake_summary.bump
+    )]
+    pub user_stake_summary: Account<'info, UserStakeSummary>,
+    
+    #[account(
+        mut,
+        constraint = rent_payer.key() == user_stake.rent_payer @ IdoError::Unauthorized
+    )]
+    /// CHECK: Validated against the payer recorded on the position
+    pub rent_payer: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshStakeSummary<'info> {
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKE_SUMMARY,
+            user_stake_summary.user.as_ref()
+        ],
+        bump = user_stake_summary.bump
+    )]
+    pub user_stake_summary: Account<'info, UserStakeSummary>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct AddStakingMint<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + StakingMintConfig::INIT_SPACE,
+        seeds = [
+            SEED_PREFIX_STAKING_MINT,
+            staking_token_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub staking_mint_config: Account<'info, StakingMintConfig>,
+    
+    pub staking_token_mint: Account<'info, Mint>,
+    
+    #[account(address = global_state.staking_token_mint)]
+    pub native_token_mint: Account<'info, Mint>,
+    
+    // Pool reserve of the native token, passed only when adding an LP mint
+    #[account(
+        constraint = lp_reserve_account.mint == global_state.staking_token_mint @ IdoError::InvalidLpReserveAccount
+    )]
+    pub lp_reserve_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStakingMint<'info> {
+    #[account(
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_MINT,
+            staking_mint_config.mint.as_ref()
+        ],
+        bump = staking_mint_config.bump
+    )]
+    pub staking_mint_config: Account<'info, StakingMintConfig>,
+    
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateLpRate<'info> {
+    #[account(
+        mut,
+        seeds = [
+            SEED_PREFIX_STAKING_MINT,
+            staking_mint_config.mint.as_ref()
+        ],
+        bump = staking_mint_config.bump,
+        constraint = staking_mint_config.is_lp_token @ IdoError::InvalidLpReserveAccount
+    )]
+    pub staking_mint_config: Account<'info, StakingMintConfig>,
+    
+    #[account(address = staking_mint_config.mint)]
+    pub staking_token_mint: Account<'info, Mint>,
+    
+    #[account(
+        address = staking_mint_config.lp_reserve_account @ IdoError::InvalidLpReserveAccount
+    )]
+    pub lp_reserve_account: Account<'info, TokenAccount>,
+}
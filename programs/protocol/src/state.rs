@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::*;
 
 #[account]
+#[derive(InitSpace)]
 pub struct Presale {
     pub admin: Pubkey,                      // Admin who can approve and manage presales
     pub creator: Pubkey,                    // Creator of the presale
@@ -29,20 +32,241 @@ pub struct Presale {
     pub second_release_time: i64,           // Time of second release
     pub third_release_time: i64,            // Time of third release
     pub bump: u8,                           // PDA bump
+    pub fcfs_enabled: bool,                 // Whether a FCFS round follows the main window
+    pub fcfs_start_time: i64,               // Start time of the FCFS round
+    pub fcfs_end_time: i64,                 // End time of the FCFS round
+    pub fcfs_wallet_cap: u64,               // Max tokens a single wallet can buy during FCFS
+    pub sale_mode: u8,                      // 0: Fixed price, 1: Overflow, 2: Dutch auction, 3: Batch auction, 4: Bonding curve
+    pub total_committed: u64,               // Total SOL committed in overflow mode
+    pub auction_start_price: u64,           // Dutch auction opening price in lamports per token
+    pub auction_floor_price: u64,           // Dutch auction floor price in lamports per token
+    pub auction_last_price: u64,            // Price paid by the latest Dutch auction buyer
+    pub bid_count: u64,                     // Number of batch auction bids placed
+    pub batch_cleared: bool,                // Whether the batch clearing price has been computed
+    pub clearing_price: u64,                // Uniform batch auction clearing price
+    pub batch_supply_at_clearing: u64,      // Tokens left for bids at exactly the clearing price
+    pub batch_demand_at_clearing: u64,      // Tokens bid at exactly the clearing price
+    pub curve_type: u8,                     // 0: Linear, 1: Exponential
+    pub curve_base_price: u64,              // Bonding curve price of the first step
+    pub curve_step_size: u64,               // Tokens sold per bonding curve price step
+    pub curve_step_increment: u64,          // Per-step increase (lamports if linear, bps if exponential)
+    pub curve_graduation_threshold: u64,    // SOL raised at which the curve graduates to listing
+    pub protocol_fee_collected: bool,       // Whether the protocol fee has been paid out
+    pub no_show_cooldown: i64,              // Tier cooldown for registrants who never buy, 0 if off
+    pub no_show_slash_bps: u16,             // Share of stake forfeited by no-shows, 0 if off
+    pub open_user_infos: u64,               // Registrations whose accounts are still open
+    pub version: u8,                        // Account layout version
+    pub presale_id: u64,                    // Global presale number, part of the PDA seeds
+    pub project: Pubkey,                    // Project grouping the rounds for this mint
+    pub vesting_percentages: [u8; 3],       // Share of each vesting release for this round
+    pub allowlist_enabled: bool,            // Eligibility by allowlist instead of tiers
+    pub previous_round_end_time: i64,       // End of the project's previous round at creation
+    pub listing_deposit: u64,               // Refundable SOL deposit held until review
+    pub legacy_seeds: bool,                 // Created before the id was part of the PDA seeds
+    pub batch_failed: bool,                 // Batch auction missed its clearing window; bids are refunded
+    pub open_settlements: u32,              // Commitments and bids not yet settled or refunded
+    pub tokens_for_liquidity: u64,          // Tokens reserved to pair with the raise at listing
+    pub reserved: [u8; 64],                 // Space for future fields
 }
 
+impl Presale {
+    /// End of the last buying window, the FCFS round included.
+    pub fn sale_end_time(&self) -> i64 {
+        if self.fcfs_enabled {
+            self.end_time.max(self.fcfs_end_time)
+        } else {
+            self.end_time
+        }
+    }
+
+    /// Tokens the creator deposits: the sale supply plus the liquidity
+    /// reserve.
+    pub fn token_deposit(&self) -> Result<u64> {
+        self.tokens_for_sale
+            .checked_add(self.tokens_for_liquidity)
+            .ok_or(error!(IdoError::MathOverflow))
+    }
+
+    /// Id bytes in the presale PDA seeds. Presales created before ids existed
+    /// derive their addresses without one.
+    pub fn id_seed(&self) -> Vec<u8> {
+        if self.legacy_seeds {
+            Vec::new()
+        } else {
+            self.presale_id.to_le_bytes().to_vec()
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Project {
+    pub mint: Pubkey,                       // Token sold across the project's rounds
+    pub creator: Pubkey,                    // Creator of every round
+    pub round_count: u32,                   // Number of presale rounds created
+    pub bump: u8,                           // PDA bump
+    pub version: u8,                        // Account layout version
+    pub tokens_deposited: u64,              // Tokens the creator has put into the project
+    pub tokens_allocated: u64,              // Tokens handed to rounds, never above the deposit
+    pub last_round_end_time: i64,           // End of the latest round; the next must start after it
+    pub reserved: [u8; 40],                 // Space for future fields
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistEntry {
+    pub presale: Pubkey,                    // Allowlisted round
+    pub user: Pubkey,                       // Allowlisted wallet
+    pub allocation: u64,                    // Tokens the wallet may buy in the round
+    pub bump: u8,                           // PDA bump
+}
+
+
+#[account]
+#[derive(InitSpace)]
+pub struct PresaleMetadata {
+    pub presale: Pubkey,                    // Presale described by this account
+    #[max_len(METADATA_MAX_NAME_LEN)]
+    pub name: String,                       // Project name
+    #[max_len(METADATA_MAX_SYMBOL_LEN)]
+    pub symbol: String,                     // Token symbol
+    #[max_len(METADATA_MAX_URI_LEN)]
+    pub uri: String,                        // Off-chain JSON with website, socials and docs
+    pub content_hash: [u8; 32],             // SHA-256 of the document at `uri`
+    pub bump: u8,                           // PDA bump
+}
+
+// Parameters passed to create_presale
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct CreatePresaleParams {
+    pub tokens_for_sale: u64,
+    pub tokens_for_liquidity: u64,
+    pub token_price: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub registration_start_time: i64,
+    pub registration_end_time: i64,
+    pub listing_price: u64,
+    pub vesting_enabled: bool,
+}
+
+// Parameters replaced by update_presale
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct UpdatePresaleParams {
+    pub tokens_for_sale: u64,
+    pub tokens_for_liquidity: u64,
+    pub token_price: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub registration_start_time: i64,
+    pub registration_end_time: i64,
+    pub listing_price: u64,
+}
+
+// Metadata passed to create_presale and update_presale_metadata
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PresaleMetadataArgs {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub content_hash: [u8; 32],
+}
 
 #[account]
+#[derive(InitSpace)]
 pub struct UserStake {
     pub user: Pubkey,                       // User wallet
-    pub staking_token_mint: Pubkey,         // Staking token mint (SFUND or XToken)
+    pub staking_token_mint: Pubkey,         // Staking token mint (any mint in the registry)
     pub amount: u64,                        // Amount staked
-    pub lock_time: i64,                     // Time when tokens were locked
-    pub tier: u8,                           // User's tier based on locked amount
+    pub lock_time: i64,                     // Amount-weighted time when tokens were locked
+    pub legacy_tier: u8,                    // Tier stored by the single-stake layout, no longer read
+    pub bump: u8,                           // PDA bump
+    pub position_index: u32,                // Index of this position among the user's stakes
+    pub effective_amount: u64,              // Weighted amount counted in the user's stake summary
+    pub lock_end: i64,                      // End of the fixed-term lock, 0 if flexible
+    pub lock_weight_bps: u16,               // Tier weight boost while the lock is active
+    pub mint_weight_bps: u16,               // Tier weight of the staking mint at the last update
+    pub power_amount: u64,                  // Amount in underlying tokens (LP positions) at the last update
+    pub reward_debt: u128,                  // Rewards already accounted for at the current amount
+    pub pending_rewards: u64,               // Settled rewards not yet claimed
+    pub sol_reward_debt: u128,              // Fee revenue already accounted for at the current amount
+    pub pending_sol_rewards: u64,           // Settled fee revenue (lamports) not yet claimed
+    pub rent_payer: Pubkey,                 // Wallet refunded when the position is closed
+    pub version: u8,                        // Account layout version
+    pub legacy_seeds: bool,                 // Created before the index was part of the PDA seeds
+    pub reserved: [u8; 64],                 // Space for future fields
+}
+
+impl UserStake {
+    /// Index bytes in the position PDA seeds. Single-stake accounts from
+    /// before positions existed derive their addresses without one.
+    pub fn index_seed(&self) -> Vec<u8> {
+        if self.legacy_seeds {
+            Vec::new()
+        } else {
+            self.position_index.to_le_bytes().to_vec()
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct UserStakeSummary {
+    pub user: Pubkey,                       // User wallet
+    pub total_power: u64,                   // Mint-weighted power across all positions, in native token units
+    pub effective_amount: u64,              // Sum of the positions' weighted amounts
+    pub tier: u8,                           // Tier derived from the effective amount
+    pub position_count: u32,                // Number of open stake positions
+    pub next_position_index: u32,           // Index used by the next new position
+    pub boost_expiry: i64,                  // Earliest end of an active lock boost, i64::MAX if none
+    pub delegate: Pubkey,                   // Wallet allowed to register and buy for the user, default if none
+    pub tier_cooldown_until: i64,           // No tier eligibility before this time (no-show penalty)
+    pub bump: u8,                           // PDA bump
+}
+
+impl UserStakeSummary {
+    /// Whether `authority` may register and buy for the owner: the owner
+    /// themselves or their delegate. Unstaking stays with the owner.
+    pub fn is_authorized(&self, authority: Pubkey) -> bool {
+        authority == self.user
+            || (self.delegate != Pubkey::default() && authority == self.delegate)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakingMintConfig {
+    pub mint: Pubkey,                       // Accepted staking mint
+    pub weight_bps: u16,                    // Tier power per staked token, in bps
+    pub enabled: bool,                      // Whether new stakes are accepted
+    pub is_lp_token: bool,                  // Whether the mint is an LP token of the native token pool
+    pub lp_reserve_account: Pubkey,         // Pool token account holding the native token reserve
+    pub bump: u8,                           // PDA bump
+    pub lp_rate: u128,                      // Native tokens per LP token, scaled by LP_RATE_PRECISION
+    pub lp_rate_updated_at: i64,            // Last time the LP rate moved towards the spot rate
+    pub decimals: u8,                       // Decimals of the accepted mint
+    pub native_decimals: u8,                // Decimals of the native staking token
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakingPool {
+    pub staking_token_mint: Pubkey,         // Mint staked into this pool
+    pub reward_mint: Pubkey,                // Mint paid out as staking rewards
+    pub reward_vault: Pubkey,               // Token account holding reward emissions
+    pub total_staked: u64,                  // Total amount staked across all users
+    pub reward_rate: u64,                   // Reward tokens emitted per second
+    pub acc_reward_per_share: u128,         // Accumulated rewards per staked token, scaled
+    pub last_update_time: i64,              // Last time the accumulator was updated
+    pub acc_sol_per_share: u128,            // Accumulated fee revenue per staked token, scaled
     pub bump: u8,                           // PDA bump
+    pub sol_reward_rate: u128,              // Fee revenue streamed per second, scaled
+    pub sol_reward_end_time: i64,           // When the current fee revenue stream runs out
+    pub rewards_remaining: u64,             // Funded reward tokens not yet emitted
 }
 
 #[account]
+#[derive(InitSpace)]
 pub struct UserPresaleInfo {
     pub user: Pubkey,                       // User wallet
     pub presale: Pubkey,                    // Presale account
@@ -53,15 +277,81 @@ pub struct UserPresaleInfo {
     pub second_claim_processed: bool,       // Whether second claim has been processed
     pub third_claim_processed: bool,        // Whether third claim has been processed
     pub bump: u8,                           // PDA bump
+    pub fcfs_purchased: u64,                // Amount purchased during the FCFS round
+    pub sol_committed: u64,                 // SOL committed pending settlement
+    pub settled: bool,                      // Whether the commitment has been settled
+    pub penalized: bool,                    // Whether the no-show penalty has been applied
+    pub rent_payer: Pubkey,                 // Wallet refunded when the account is closed
+    pub version: u8,                        // Account layout version
+    pub registered_tier: u8,                // Tier the user registered with, 0 for allowlisted rounds
+    pub auction_payer: Pubkey,              // Wallet that paid the Dutch auction commitment
+    pub reserved: [u8; 31],                 // Space for future fields
+}
+
+impl UserPresaleInfo {
+    /// Wallet the Dutch auction over-commit is refunded to. Accounts from
+    /// before the payer was recorded were always paid by the user.
+    pub fn refund_recipient(&self) -> Pubkey {
+        if self.auction_payer == Pubkey::default() {
+            self.user
+        } else {
+            self.auction_payer
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Bid {
+    pub bidder: Pubkey,                     // Bidder wallet
+    pub presale: Pubkey,                    // Presale account
+    pub price: u64,                         // Bid price in lamports per token
+    pub quantity: u64,                      // Number of tokens bid for
+    pub deposit: u64,                       // SOL escrowed for the bid
+    pub settled: bool,                      // Whether the bid has been settled
+    pub bump: u8,                           // PDA bump
 }
 
 #[account]
+#[derive(InitSpace)]
+pub struct BatchOrderBook {
+    pub presale: Pubkey,                    // Batch auction this book belongs to
+    pub tick_size: u64,                     // Lamports between price levels, starting at the reserve price
+    pub level_demand: [u64; BATCH_PRICE_LEVELS], // Tokens bid at each price level
+    pub bump: u8,                           // PDA bump
+}
+
+#[account]
+#[derive(InitSpace)]
 pub struct GlobalState {
     pub admin: Pubkey,                      // Program admin
-    pub staking_token_mint: Pubkey,         // SFUND or XToken mint
+    pub staking_token_mint: Pubkey,         // Primary staking mint (SFUND or XToken)
     pub treasury_wallet: Pubkey,            // Treasury wallet for protocol fees
     pub total_presales: u64,                // Total number of presales created
     pub active_presales: u64,               // Number of active presales
     pub total_stakers: u64,                 // Total number of stakers
     pub bump: u8,                           // PDA bump
+    pub staker_fee_share_bps: u16,          // Share of protocol fees paid to stakers
+    pub total_staked: u64,                  // Mint-weighted power across all stakers, in native token units
+    pub tier_stakers: [u64; TIER_COUNT],    // Stakers per tier, index 0 is below tier 1
+    pub presales_by_status: [u64; PRESALE_STATUS_COUNT], // Presales per status code
+    pub version: u8,                        // Account layout version
+    pub application_fee: u64,               // Non-refundable fee charged on create_presale
+    pub application_fee_in_staking_token: bool, // Fee paid in the staking token instead of SOL
+    pub listing_deposit: u64,               // SOL deposit returned on approval, kept on rejection
+    pub listing_band_min_bps: u16,          // Lowest listing price vs sale price, 0 means at par
+    pub listing_band_max_bps: u16,          // Highest listing price vs sale price, 0 means unbounded
+    pub staking_pool_count: u64,            // Staking pools sharing protocol fee revenue
+    pub reserved: [u8; 35],                 // Space for future fields
+}
+
+// Returned by the get_protocol_stats view
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ProtocolStats {
+    pub total_presales: u64,
+    pub active_presales: u64,
+    pub presales_by_status: [u64; PRESALE_STATUS_COUNT],
+    pub total_stakers: u64,
+    pub total_staked: u64,
+    pub tier_stakers: [u64; TIER_COUNT],
 }
\ No newline at end of file
@@ -16,21 +16,135 @@ pub fn get_tier_for_amount(amount: u64) -> u8 {
     }
 }
 
+/// Multiplier applied to a stake based on how long it has been held.
+/// Flash stakes are discounted, long-term stakes are boosted.
+pub fn get_loyalty_multiplier_bps(stake_duration: i64) -> u64 {
+    if stake_duration >= LOYALTY_LONG_TERM_DURATION {
+        LOYALTY_LONG_TERM_MULTIPLIER_BPS
+    } else if stake_duration >= LOYALTY_MEDIUM_TERM_DURATION {
+        LOYALTY_MEDIUM_TERM_MULTIPLIER_BPS
+    } else if stake_duration >= LOYALTY_SHORT_TERM_DURATION {
+        LOYALTY_SHORT_TERM_MULTIPLIER_BPS
+    } else if stake_duration >= LOYALTY_FLASH_STAKE_DURATION {
+        LOYALTY_BASE_MULTIPLIER_BPS
+    } else {
+        LOYALTY_FLASH_STAKE_MULTIPLIER_BPS
+    }
+}
+
+/// Lock duration and tier weight for a fixed-term lock option.
+pub fn get_lock_term(lock_term: u8) -> Result<(i64, u16)> {
+    match lock_term {
+        LOCK_TERM_FLEXIBLE => Ok((0, LOCK_TERM_FLEXIBLE_WEIGHT_BPS)),
+        LOCK_TERM_30_DAYS => Ok((LOCK_TERM_30_DAYS_DURATION, LOCK_TERM_30_DAYS_WEIGHT_BPS)),
+        LOCK_TERM_90_DAYS => Ok((LOCK_TERM_90_DAYS_DURATION, LOCK_TERM_90_DAYS_WEIGHT_BPS)),
+        LOCK_TERM_180_DAYS => Ok((LOCK_TERM_180_DAYS_DURATION, LOCK_TERM_180_DAYS_WEIGHT_BPS)),
+        _ => err!(IdoError::InvalidLockTerm),
+    }
+}
+
+/// The lock boost only applies while the position is still locked.
+pub fn get_lock_weight_bps(user_stake: &UserStake, current_time: i64) -> u64 {
+    if current_time < user_stake.lock_end {
+        user_stake.lock_weight_bps as u64
+    } else {
+        LOCK_TERM_FLEXIBLE_WEIGHT_BPS as u64
+    }
+}
+
+/// Native tokens in the pool reserve per LP token right now, scaled by
+/// `LP_RATE_PRECISION`.
+pub fn get_lp_spot_rate(reserve_amount: u64, lp_supply: u64) -> u128 {
+    if lp_supply == 0 {
+        return 0;
+    }
+
+    (reserve_amount as u128)
+        .checked_mul(LP_RATE_PRECISION)
+        .unwrap()
+        .checked_div(lp_supply as u128)
+        .unwrap()
+}
+
+/// Moves the stored LP rate towards the spot rate by at most
+/// `LP_RATE_MAX_CHANGE_BPS`, so a reserve skewed for one transaction (a
+/// flash swap or a donation) can only nudge the valuation.
+pub fn get_bounded_lp_rate(current_rate: u128, spot_rate: u128) -> u128 {
+    let max_change = current_rate
+        .checked_mul(LP_RATE_MAX_CHANGE_BPS as u128)
+        .unwrap()
+        .checked_div(BPS_DENOMINATOR as u128)
+        .unwrap();
+
+    spot_rate.clamp(
+        current_rate.saturating_sub(max_change),
+        current_rate.checked_add(max_change).unwrap(),
+    )
+}
+
+/// Converts `amount` of a mint with `decimals` into native token units so
+/// positions in different mints can be added up.
+pub fn normalize_to_native_decimals(amount: u64, decimals: u8, native_decimals: u8) -> u64 {
+    let scaled = if decimals >= native_decimals {
+        (amount as u128)
+            .checked_div(10u128.pow((decimals - native_decimals) as u32))
+            .unwrap()
+    } else {
+        (amount as u128)
+            .checked_mul(10u128.pow((native_decimals - decimals) as u32))
+            .unwrap()
+    };
+
+    u64::try_from(scaled).unwrap()
+}
+
+/// Native tokens backing `lp_amount` LP tokens at the stored LP rate.
+pub fn get_lp_underlying_amount(lp_amount: u64, lp_rate: u128) -> u64 {
+    (lp_amount as u128)
+        .checked_mul(lp_rate)
+        .unwrap()
+        .checked_div(LP_RATE_PRECISION)
+        .unwrap() as u64
+}
+
+/// Position power weighted by the staking mint, in native token units. This
+/// is what summaries and global totals add up across mints.
+pub fn get_weighted_power(user_stake: &UserStake) -> u64 {
+    (user_stake.power_amount as u128)
+        .checked_mul(user_stake.mint_weight_bps as u128)
+        .unwrap()
+        .checked_div(BPS_DENOMINATOR as u128)
+        .unwrap() as u64
+}
+
+/// Stake amount weighted by the staking mint, by holding time and by any
+/// active fixed-term lock.
+pub fn get_effective_stake(user_stake: &UserStake, current_time: i64) -> u64 {
+    let loyalty = get_loyalty_multiplier_bps(current_time.saturating_sub(user_stake.lock_time));
+    let lock_weight = get_lock_weight_bps(user_stake, current_time);
+
+    (get_weighted_power(user_stake) as u128)
+        .checked_mul(loyalty as u128)
+        .unwrap()
+        .checked_mul(lock_weight as u128)
+        .unwrap()
+        .checked_div((BPS_DENOMINATOR as u128) * (BPS_DENOMINATOR as u128))
+        .unwrap() as u64
+}
+
+/// Splits the sale between the tier pools. Tier 3 takes the rounding
+/// remainder so the pools always add up to the whole sale.
 pub fn calculate_presale_tier_allocations(total_tokens_for_sale: u64) -> (u64, u64, u64) {
     let tier1_allocation = total_tokens_for_sale * TIER_1_ALLOCATION_PERCENTAGE as u64 / 100;
     let tier2_allocation = total_tokens_for_sale * TIER_2_ALLOCATION_PERCENTAGE as u64 / 100;
-    let tier3_allocation = total_tokens_for_sale * TIER_3_ALLOCATION_PERCENTAGE as u64 / 100;
+    let tier3_allocation = total_tokens_for_sale - tier1_allocation - tier2_allocation;
     
     (tier1_allocation, tier2_allocation, tier3_allocation)
 }
 
-pub fn check_tier_eligibility(
-    user_stake: &Account<UserStake>,
-    presale: &Account<Presale>,
-) -> Result<()> {
+pub fn check_registration_open(presale: &Account<Presale>) -> Result<()> {
     let current_time = Clock::get()?.unix_timestamp;
     
-    // Ensure registration is open
     require!(
         current_time >= presale.registration_start_time,
         IdoError::RegistrationNotStarted
@@ -41,13 +155,51 @@ pub fn check_tier_eligibility(
         IdoError::RegistrationEnded
     );
     
+    Ok(())
+}
+
+/// Returns the tier the user registers with.
+pub fn check_tier_eligibility(
+    summary: &Account<UserStakeSummary>,
+    presale: &Account<Presale>,
+) -> Result<u8> {
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    // Ensure registration is open
+    check_registration_open(presale)?;
+    
     // Check if user has staked enough for at least tier 1
+    let tier = get_summary_tier(summary, current_time)?;
+    require!(tier > 0, IdoError::InsufficientTierQualification);
+    
+    Ok(tier)
+}
+    
+/// Whether any tier pool open to `user_tier` still had tokens left, i.e.
+/// the user could have bought but didn't.
+pub fn has_unsold_tier_allocation(user_tier: u8, presale: &Presale) -> bool {
+    (1..=user_tier.min(3)).any(|tier| match tier {
+        1 => presale.tier1_sold < presale.tier1_allocation,
+        2 => presale.tier2_sold < presale.tier2_allocation,
+        _ => presale.tier3_sold < presale.tier3_allocation,
+    })
+}
+
+/// Tier recorded on the user's stake summary. Fails if a lock boost counted
+/// in the summary has since expired and the summary hasn't been refreshed,
+/// or while a no-show cooldown is running.
+pub fn get_summary_tier(summary: &UserStakeSummary, current_time: i64) -> Result<u8> {
     require!(
-        user_stake.tier > 0,
-        IdoError::InsufficientTierQualification
+        current_time < summary.boost_expiry,
+        IdoError::StakeSummaryStale
     );
     
-    Ok(())
+    require!(
+        current_time >= summary.tier_cooldown_until,
+        IdoError::TierCooldownActive
+    );
+    
+    Ok(summary.tier)
 }
 
 pub fn can_purchase_from_tier(
@@ -1,3 +1,4 @@
+use crate::constants::*;
 use crate::state::*;
 use crate::errors::*;
 use anchor_lang::prelude::*;
@@ -15,7 +16,18 @@ pub fn initialize_global_state(
     global_state.total_presales = 0;
     global_state.active_presales = 0;
     global_state.total_stakers = 0;
+    global_state.staker_fee_share_bps = 0;
+    global_state.total_staked = 0;
+    global_state.tier_stakers = [0; TIER_COUNT];
+    global_state.presales_by_status = [0; PRESALE_STATUS_COUNT];
+    global_state.application_fee = 0;
+    global_state.application_fee_in_staking_token = false;
+    global_state.listing_deposit = 0;
+    global_state.listing_band_min_bps = 0;
+    global_state.listing_band_max_bps = 0;
+    global_state.staking_pool_count = 0;
     global_state.bump = ctx.bumps.global_state;
+    global_state.version = ACCOUNT_VERSION;
     msg!("Global state initialized successfully");
     
     Ok(())
@@ -43,6 +55,158 @@ pub fn update_admin(
 }
 
 
+pub fn set_staker_fee_share(
+    ctx: Context<UpdateGlobalConfig>,
+    staker_fee_share_bps: u16,
+) -> Result<()> {
+    require!(
+        staker_fee_share_bps as u64 <= BPS_DENOMINATOR,
+        IdoError::InvalidBasisPoints
+    );
+
+    let global_state = &mut ctx.accounts.global_state;
+    global_state.staker_fee_share_bps = staker_fee_share_bps;
+
+    msg!("Staker fee share set to {} bps", staker_fee_share_bps);
+
+    Ok(())
+}
+
+
+/// Sets what creators pay to apply. The fee goes to the treasury; the
+/// deposit is held by the presale until the admin reviews it.
+pub fn set_application_fees(
+    ctx: Context<UpdateGlobalConfig>,
+    application_fee: u64,
+    application_fee_in_staking_token: bool,
+    listing_deposit: u64,
+) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    global_state.application_fee = application_fee;
+    global_state.application_fee_in_staking_token = application_fee_in_staking_token;
+    global_state.listing_deposit = listing_deposit;
+
+    msg!(
+        "Application fee set to {} ({}), listing deposit set to {} lamports",
+        application_fee,
+        if application_fee_in_staking_token { "staking token" } else { "lamports" },
+        listing_deposit
+    );
+
+    Ok(())
+}
+
+
+/// Bounds the listing price relative to the sale price, in bps of the sale
+/// price. A zero minimum keeps listings at or above the sale price and a
+/// zero maximum leaves them uncapped.
+pub fn set_listing_price_band(
+    ctx: Context<UpdateGlobalConfig>,
+    listing_band_min_bps: u16,
+    listing_band_max_bps: u16,
+) -> Result<()> {
+    require!(
+        listing_band_max_bps == 0 || listing_band_min_bps <= listing_band_max_bps,
+        IdoError::InvalidBasisPoints
+    );
+
+    let global_state = &mut ctx.accounts.global_state;
+    global_state.listing_band_min_bps = listing_band_min_bps;
+    global_state.listing_band_max_bps = listing_band_max_bps;
+
+    msg!(
+        "Listing price band set to {}-{} bps",
+        listing_band_min_bps,
+        listing_band_max_bps
+    );
+
+    Ok(())
+}
+
+
+pub fn get_protocol_stats(ctx: Context<GetProtocolStats>) -> Result<ProtocolStats> {
+    let global_state = &ctx.accounts.global_state;
+
+    Ok(ProtocolStats {
+        total_presales: global_state.total_presales,
+        active_presales: global_state.active_presales,
+        presales_by_status: global_state.presales_by_status,
+        total_stakers: global_state.total_stakers,
+        total_staked: global_state.total_staked,
+        tier_stakers: global_state.tier_stakers,
+    })
+}
+
+
+/// Moves a presale to `status` and keeps the per-status counters in step.
+/// Approved and live presales count as active.
+pub fn set_presale_status(
+    presale: &mut Presale,
+    global_state: &mut GlobalState,
+    status: u8,
+) {
+    let is_active = |status: u8| status == STATUS_APPROVED || status == STATUS_LIVE;
+    let previous = presale.status;
+
+    global_state.presales_by_status[previous as usize] =
+        global_state.presales_by_status[previous as usize].saturating_sub(1);
+    global_state.presales_by_status[status as usize] =
+        global_state.presales_by_status[status as usize].checked_add(1).unwrap();
+
+    if is_active(status) && !is_active(previous) {
+        global_state.active_presales = global_state.active_presales.checked_add(1).unwrap();
+    } else if !is_active(status) && is_active(previous) {
+        global_state.active_presales = global_state.active_presales.saturating_sub(1);
+    }
+
+    presale.status = status;
+}
+
+
+/// Folds a change to a user's stake summary into the global totals.
+/// `previous_total` and `previous_tier` are the summary values before the
+/// change.
+pub fn record_stake_change(
+    global_state: &mut GlobalState,
+    previous_total: u64,
+    previous_tier: u8,
+    summary: &UserStakeSummary,
+) {
+    global_state.total_staked = global_state
+        .total_staked
+        .saturating_sub(previous_total)
+        .checked_add(summary.total_power)
+        .unwrap();
+
+    if previous_total > 0 {
+        global_state.total_stakers = global_state.total_stakers.saturating_sub(1);
+        global_state.tier_stakers[previous_tier as usize] =
+            global_state.tier_stakers[previous_tier as usize].saturating_sub(1);
+    }
+
+    if summary.total_power > 0 {
+        global_state.total_stakers = global_state.total_stakers.checked_add(1).unwrap();
+        global_state.tier_stakers[summary.tier as usize] =
+            global_state.tier_stakers[summary.tier as usize].checked_add(1).unwrap();
+    }
+}
+
+
+/// Moves lamports out of a program-owned account (e.g. the presale PDA).
+/// System transfers can't debit accounts that carry data, so the balances
+/// are adjusted directly.
+pub fn transfer_lamports_from_pda(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    amount: u64,
+) -> Result<()> {
+    **from.try_borrow_mut_lamports()? = from.lamports().checked_sub(amount).unwrap();
+    **to.try_borrow_mut_lamports()? = to.lamports().checked_add(amount).unwrap();
+
+    Ok(())
+}
+
+
 #[derive(Accounts)]
 pub struct InitializeGlobalState<'info> {
     #[account(mut)]
@@ -51,7 +215,7 @@ pub struct InitializeGlobalState<'info> {
     #[account(
         init,
         payer = admin,
-        space = 8 + std::mem::size_of::<GlobalState>(),
+        space = 8 + GlobalState::INIT_SPACE,
         seeds = [b"global_state"],
         bump
     )]
@@ -74,4 +238,28 @@ pub struct UpdateAdmin<'info> {
         bump = global_state.bump
     )]
     pub global_state: Account<'info, GlobalState>,
-}
\ No newline at end of file
+}
+
+#[derive(Accounts)]
+pub struct UpdateGlobalConfig<'info> {
+    #[account(
+        constraint = admin.key() == global_state.admin @ IdoError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct GetProtocolStats<'info> {
+    #[account(
+        seeds = [b"global_state"],
+        bump = global_state.bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
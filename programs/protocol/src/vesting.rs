@@ -6,8 +6,8 @@ use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 
 
 pub fn calculate_claimable_amount(
-    user_info: &Account<UserPresaleInfo>,
-    presale: &Account<Presale>,
+    user_info: &UserPresaleInfo,
+    presale: &Presale,
 ) -> Result<(u64, u64, u64)> {
     // let current_time = Clock::get()?.unix_timestamp;
     let total_purchased = user_info.purchased;
@@ -18,23 +18,25 @@ pub fn calculate_claimable_amount(
         return Ok((first_release, 0, 0));
     }
     
-    // Calculate vesting amounts
+    // Calculate vesting amounts with the round's split
     let first_release = total_purchased
-        .checked_mul(VESTING_FIRST_RELEASE_PERCENTAGE as u64)
+        .checked_mul(presale.vesting_percentages[0] as u64)
         .unwrap()
         .checked_div(100)
         .unwrap();
     
     let second_release = total_purchased
-        .checked_mul(VESTING_SECOND_RELEASE_PERCENTAGE as u64)
+        .checked_mul(presale.vesting_percentages[1] as u64)
         .unwrap()
         .checked_div(100)
         .unwrap();
     
+    // The last release takes the rounding remainder so the three add up to
+    // the full purchase
     let third_release = total_purchased
-        .checked_mul(VESTING_THIRD_RELEASE_PERCENTAGE as u64)
+        .checked_sub(first_release)
         .unwrap()
-        .checked_div(100)
+        .checked_sub(second_release)
         .unwrap();
     
     // Return claimable amounts based on time
@@ -83,10 +85,12 @@ pub fn claim_tokens(ctx: Context<ClaimTokens>) -> Result<()> {
     );
     
     // Transfer tokens from presale token account to user
+    let presale_id = presale.id_seed();
     let seeds = &[
         SEED_PREFIX_PRESALE,
         presale.mint_of_token_being_sold.as_ref(),
         presale.creator.as_ref(),
+        presale_id.as_ref(),
         &[presale.bump],
     ];
     let signer = &[&seeds[..]];
@@ -117,7 +121,7 @@ pub fn get_upcoming_claims(
     let current_time = Clock::get()?.unix_timestamp;
     let mut upcoming_claims = Vec::new();
     
-    // Calculate vesting amounts
+    // Calculate vesting amounts with the round's split
     let (first_release, second_release, third_release) = 
         calculate_claimable_amount(user_info, presale)?;
     
@@ -147,6 +151,7 @@ pub struct ClaimTokens<'info> {
             SEED_PREFIX_PRESALE,
             presale.mint_of_token_being_sold.as_ref(),
             presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
         ],
         bump = presale.bump,
         constraint = presale.status == STATUS_COMPLETED @ IdoError::PresaleNotCompleted
@@ -0,0 +1,114 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::batch_auction::{calculate_bid_fill, find_clearing_price};
+use protocol::constants::BATCH_PRICE_LEVELS;
+use protocol::state::{BatchOrderBook, Bid, Presale};
+
+const RESERVE_PRICE: u64 = 100;
+const TICK_SIZE: u64 = 10;
+
+fn order_book(levels: &[(usize, u64)]) -> BatchOrderBook {
+    let mut level_demand = [0; BATCH_PRICE_LEVELS];
+    for &(level, quantity) in levels {
+        level_demand[level] += quantity;
+    }
+
+    BatchOrderBook {
+        presale: Pubkey::new_unique(),
+        tick_size: TICK_SIZE,
+        level_demand,
+        bump: 255,
+    }
+}
+
+fn bid(price: u64, quantity: u64) -> Bid {
+    Bid {
+        bidder: Pubkey::new_unique(),
+        presale: Pubkey::new_unique(),
+        price,
+        quantity,
+        deposit: price * quantity,
+        settled: false,
+        bump: 255,
+    }
+}
+
+fn cleared_presale(book: &BatchOrderBook, supply: u64) -> Presale {
+    let mut presale = zeroed::<Presale>();
+    let (clearing_price, supply_at_clearing, demand_at_clearing) =
+        find_clearing_price(book, RESERVE_PRICE, supply);
+    presale.tokens_for_sale = supply;
+    presale.clearing_price = clearing_price;
+    presale.batch_supply_at_clearing = supply_at_clearing;
+    presale.batch_demand_at_clearing = demand_at_clearing;
+    presale.batch_cleared = true;
+    presale
+}
+
+#[test]
+fn oversubscribed_book_clears_at_the_marginal_level() {
+    // 400 @ 150, 500 @ 130, 800 @ 110 against 1,000 tokens
+    let book = order_book(&[(5, 400), (3, 500), (1, 800)]);
+
+    assert_eq!(find_clearing_price(&book, RESERVE_PRICE, 1_000), (110, 100, 800));
+}
+
+#[test]
+fn bids_at_the_clearing_price_share_the_remainder() {
+    let book = order_book(&[(5, 400), (3, 500), (1, 600), (1, 200)]);
+    let presale = cleared_presale(&book, 1_000);
+
+    // Above the clearing price fills in full, at the uniform price
+    let winner = bid(150, 400);
+    assert_eq!(calculate_bid_fill(&winner, &presale), 400);
+    assert_eq!(winner.deposit - 400 * presale.clearing_price, 16_000);
+
+    // 100 tokens left at 110 are split 600:200
+    assert_eq!(calculate_bid_fill(&bid(110, 600), &presale), 75);
+    assert_eq!(calculate_bid_fill(&bid(110, 200), &presale), 25);
+
+    // Below the clearing price gets nothing back but the deposit
+    assert_eq!(calculate_bid_fill(&bid(100, 50), &presale), 0);
+}
+
+#[test]
+fn undersubscribed_book_fills_every_bid_at_the_lowest_price() {
+    let book = order_book(&[(4, 300), (2, 200)]);
+    let presale = cleared_presale(&book, 1_000);
+
+    assert_eq!(presale.clearing_price, 120);
+    assert_eq!(calculate_bid_fill(&bid(140, 300), &presale), 300);
+    assert_eq!(calculate_bid_fill(&bid(120, 200), &presale), 200);
+}
+
+#[test]
+fn exactly_subscribed_book_fills_the_marginal_level_in_full() {
+    let book = order_book(&[(2, 600), (0, 400)]);
+
+    assert_eq!(find_clearing_price(&book, RESERVE_PRICE, 1_000), (100, 400, 400));
+}
+
+#[test]
+fn empty_book_clears_at_the_reserve_price() {
+    let book = order_book(&[]);
+
+    assert_eq!(find_clearing_price(&book, RESERVE_PRICE, 1_000), (RESERVE_PRICE, 0, 0));
+}
+
+#[test]
+fn filled_tokens_never_exceed_the_supply() {
+    let levels = [(63, 7), (40, 333), (40, 334), (12, 999), (12, 1), (0, 5_000)];
+    let book = order_book(&levels);
+    let presale = cleared_presale(&book, 1_000);
+
+    let filled: u64 = levels
+        .iter()
+        .map(|&(level, quantity)| {
+            calculate_bid_fill(&bid(RESERVE_PRICE + TICK_SIZE * level as u64, quantity), &presale)
+        })
+        .sum();
+
+    assert!(filled <= presale.tokens_for_sale);
+}
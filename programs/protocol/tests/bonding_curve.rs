@@ -0,0 +1,76 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::bonding_curve::calculate_curve_integral;
+use protocol::constants::{CURVE_TYPE_EXPONENTIAL, CURVE_TYPE_LINEAR};
+use protocol::state::Presale;
+
+fn curve(curve_type: u8, base_price: u64, step_size: u64, step_increment: u64) -> Presale {
+    let mut presale = zeroed::<Presale>();
+    presale.curve_type = curve_type;
+    presale.curve_base_price = base_price;
+    presale.curve_step_size = step_size;
+    presale.curve_step_increment = step_increment;
+    presale.tokens_for_sale = 1_000;
+    presale
+}
+
+// Mirrors the cost and proceeds computed by buy_from_curve and sell_to_curve
+fn buy_cost(presale: &Presale, supply: u64, amount: u64) -> u64 {
+    calculate_curve_integral(presale, supply + amount).unwrap()
+        - calculate_curve_integral(presale, supply).unwrap()
+}
+
+fn sell_proceeds(presale: &Presale, supply: u64, amount: u64) -> u64 {
+    calculate_curve_integral(presale, supply).unwrap()
+        - calculate_curve_integral(presale, supply - amount).unwrap()
+}
+
+#[test]
+fn linear_curve_prices_each_step() {
+    // 100 lamports for the first 10 tokens, then +5 per step
+    let presale = curve(CURVE_TYPE_LINEAR, 100, 10, 5);
+
+    assert_eq!(calculate_curve_integral(&presale, 0).unwrap(), 0);
+    assert_eq!(calculate_curve_integral(&presale, 10).unwrap(), 1_000);
+    assert_eq!(calculate_curve_integral(&presale, 25).unwrap(), 1_000 + 1_050 + 5 * 110);
+}
+
+#[test]
+fn exponential_curve_compounds_each_step() {
+    // +10% per step of 10 tokens
+    let presale = curve(CURVE_TYPE_EXPONENTIAL, 100, 10, 1_000);
+
+    assert_eq!(calculate_curve_integral(&presale, 10).unwrap(), 1_000);
+    assert_eq!(calculate_curve_integral(&presale, 20).unwrap(), 1_000 + 1_100);
+    assert_eq!(calculate_curve_integral(&presale, 25).unwrap(), 2_100 + 5 * 121);
+}
+
+#[test]
+fn selling_back_returns_what_was_paid() {
+    for presale in [
+        curve(CURVE_TYPE_LINEAR, 100, 10, 5),
+        curve(CURVE_TYPE_EXPONENTIAL, 100, 10, 1_000),
+    ] {
+        // Another buyer moved the curve first
+        let supply = 37;
+        let cost = buy_cost(&presale, supply, 58);
+
+        assert!(cost > 0);
+        assert_eq!(sell_proceeds(&presale, supply + 58, 58), cost);
+    }
+}
+
+#[test]
+fn splitting_a_buy_costs_the_same() {
+    for presale in [
+        curve(CURVE_TYPE_LINEAR, 100, 10, 5),
+        curve(CURVE_TYPE_EXPONENTIAL, 100, 10, 1_000),
+    ] {
+        let whole = buy_cost(&presale, 0, 95);
+        let split = buy_cost(&presale, 0, 13) + buy_cost(&presale, 13, 40) + buy_cost(&presale, 53, 42);
+
+        assert_eq!(split, whole);
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::zeroed;
+use protocol::constants::*;
+use protocol::presale::{is_presale_closable, is_user_presale_info_settled};
+use protocol::state::{Presale, UserPresaleInfo};
+use protocol::vesting::calculate_claimable_amount;
+
+fn vested_sale() -> Presale {
+    let mut presale = zeroed::<Presale>();
+    presale.vesting_enabled = true;
+    presale.vesting_percentages = [40, 30, 30];
+    presale
+}
+
+fn buyer(purchased: u64) -> UserPresaleInfo {
+    let mut user_info = zeroed::<UserPresaleInfo>();
+    user_info.purchased = purchased;
+    user_info
+}
+
+fn finished_sale() -> Presale {
+    let mut presale = zeroed::<Presale>();
+    presale.status = STATUS_COMPLETED;
+    presale.is_listed = true;
+    presale.protocol_fee_collected = true;
+    presale
+}
+
+#[test]
+fn vested_releases_add_up_to_the_purchase() {
+    let presale = vested_sale();
+    let mut user_info = buyer(7);
+
+    let (first, second, third) = calculate_claimable_amount(&user_info, &presale).unwrap();
+    assert_eq!((first, second, third), (2, 2, 3));
+
+    for release in [first, second, third] {
+        assert!(!is_user_presale_info_settled(&user_info));
+        user_info.claimed += release;
+    }
+    user_info.first_claim_processed = true;
+    user_info.second_claim_processed = true;
+    user_info.third_claim_processed = true;
+
+    assert_eq!(user_info.claimed, 7);
+    assert!(is_user_presale_info_settled(&user_info));
+}
+
+#[test]
+fn registrations_that_took_every_release_close() {
+    // Claimed before releases took the rounding remainder
+    let mut user_info = buyer(7);
+    user_info.claimed = 6;
+    user_info.first_claim_processed = true;
+    user_info.second_claim_processed = true;
+    assert!(!is_user_presale_info_settled(&user_info));
+
+    user_info.third_claim_processed = true;
+    assert!(is_user_presale_info_settled(&user_info));
+}
+
+#[test]
+fn unsettled_commitments_keep_the_registration_open() {
+    let mut user_info = buyer(0);
+    user_info.sol_committed = 1_000;
+    assert!(!is_user_presale_info_settled(&user_info));
+
+    user_info.settled = true;
+    assert!(is_user_presale_info_settled(&user_info));
+}
+
+#[test]
+fn presales_close_once_finished_and_empty() {
+    let mut presale = finished_sale();
+    assert!(is_presale_closable(&presale));
+
+    presale.open_user_infos = 1;
+    assert!(!is_presale_closable(&presale));
+    presale.open_user_infos = 0;
+
+    presale.protocol_fee_collected = false;
+    assert!(!is_presale_closable(&presale));
+    presale.protocol_fee_collected = true;
+
+    presale.is_listed = false;
+    assert!(!is_presale_closable(&presale));
+
+    // Cancelled sales never list
+    presale.status = STATUS_CANCELLED;
+    assert!(is_presale_closable(&presale));
+
+    presale.status = STATUS_LIVE;
+    assert!(!is_presale_closable(&presale));
+}
+
+#[test]
+fn legacy_presales_close() {
+    let mut presale = finished_sale();
+    presale.legacy_seeds = true;
+
+    assert!(is_presale_closable(&presale));
+    assert!(presale.id_seed().is_empty());
+}
@@ -0,0 +1,8 @@
+use anchor_lang::prelude::*;
+use protocol::migration::Versioned;
+
+/// An account of the current layout with every field zeroed, as a fixture
+/// to fill in.
+pub fn zeroed<T: Versioned>() -> T {
+    T::try_deserialize_unchecked(&mut &vec![0; T::SPACE][..]).unwrap()
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use protocol::state::UserStakeSummary;
+
+fn summary(user: Pubkey, delegate: Pubkey) -> UserStakeSummary {
+    UserStakeSummary {
+        user,
+        total_power: 0,
+        effective_amount: 0,
+        tier: 0,
+        position_count: 0,
+        next_position_index: 0,
+        boost_expiry: i64::MAX,
+        delegate,
+        tier_cooldown_until: 0,
+        bump: 0,
+    }
+}
+
+#[test]
+fn owner_and_delegate_can_act_for_the_user() {
+    let (user, delegate) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let summary = summary(user, delegate);
+
+    assert!(summary.is_authorized(user));
+    assert!(summary.is_authorized(delegate));
+    assert!(!summary.is_authorized(Pubkey::new_unique()));
+}
+
+#[test]
+fn no_delegate_leaves_only_the_owner() {
+    let user = Pubkey::new_unique();
+    let summary = summary(user, Pubkey::default());
+
+    assert!(summary.is_authorized(user));
+    assert!(!summary.is_authorized(Pubkey::default()));
+}
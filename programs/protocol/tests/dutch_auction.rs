@@ -0,0 +1,68 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::dutch_auction::{get_auction_clearing_price, get_current_auction_price};
+use protocol::state::{Presale, UserPresaleInfo};
+use protocol::tier::calculate_presale_tier_allocations;
+
+fn auction(tokens_for_sale: u64) -> Presale {
+    let mut presale = zeroed::<Presale>();
+    presale.tokens_for_sale = tokens_for_sale;
+    presale.start_time = 1_000;
+    presale.end_time = 2_000;
+    presale.auction_start_price = 5_000;
+    presale.auction_floor_price = 1_000;
+    presale
+}
+
+#[test]
+fn price_decays_linearly_to_the_floor() {
+    let presale = auction(1_000);
+
+    assert_eq!(get_current_auction_price(&presale, 500), 5_000);
+    assert_eq!(get_current_auction_price(&presale, 1_250), 4_000);
+    assert_eq!(get_current_auction_price(&presale, 1_500), 3_000);
+    assert_eq!(get_current_auction_price(&presale, 2_500), 1_000);
+}
+
+#[test]
+fn tier_pools_cover_the_whole_sale() {
+    for tokens_for_sale in [1, 10, 99, 1_000, 1_000_003] {
+        let (tier1, tier2, tier3) = calculate_presale_tier_allocations(tokens_for_sale);
+        assert_eq!(tier1 + tier2 + tier3, tokens_for_sale);
+    }
+}
+
+#[test]
+fn selling_every_tier_pool_clears_at_the_last_price() {
+    // 10 tokens used to leave one token outside the floored tier pools
+    let mut presale = auction(10);
+    let (tier1, tier2, tier3) = calculate_presale_tier_allocations(10);
+    presale.tokens_sold = tier1 + tier2 + tier3;
+    presale.auction_last_price = 3_200;
+
+    assert_eq!(get_auction_clearing_price(&presale), 3_200);
+}
+
+#[test]
+fn undersold_auction_clears_at_the_floor() {
+    let mut presale = auction(10);
+    presale.tokens_sold = 9;
+    presale.auction_last_price = 3_200;
+
+    assert_eq!(get_auction_clearing_price(&presale), 1_000);
+}
+
+#[test]
+fn over_commit_is_refunded_to_the_payer() {
+    let mut user_info = zeroed::<UserPresaleInfo>();
+    user_info.user = Pubkey::new_unique();
+
+    // Registrations from before the payer was recorded were paid by the user
+    assert_eq!(user_info.refund_recipient(), user_info.user);
+
+    let delegate = Pubkey::new_unique();
+    user_info.auction_payer = delegate;
+    assert_eq!(user_info.refund_recipient(), delegate);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::presale::check_fcfs_purchase;
+use protocol::state::Presale;
+
+const FCFS_START: i64 = 1_000;
+const FCFS_END: i64 = 2_000;
+
+fn fcfs_sale(tokens_for_sale: u64, tokens_sold: u64, fcfs_wallet_cap: u64) -> Presale {
+    let mut presale = zeroed::<Presale>();
+    presale.tokens_for_sale = tokens_for_sale;
+    presale.tokens_sold = tokens_sold;
+    presale.fcfs_enabled = true;
+    presale.fcfs_start_time = FCFS_START;
+    presale.fcfs_end_time = FCFS_END;
+    presale.fcfs_wallet_cap = fcfs_wallet_cap;
+    presale
+}
+
+#[test]
+fn purchases_are_limited_to_the_round_window() {
+    let presale = fcfs_sale(1_000, 0, 100);
+
+    assert!(check_fcfs_purchase(&presale, 0, 10, FCFS_START - 1).is_err());
+    assert!(check_fcfs_purchase(&presale, 0, 10, FCFS_START).is_ok());
+    assert!(check_fcfs_purchase(&presale, 0, 10, FCFS_END).is_ok());
+    assert!(check_fcfs_purchase(&presale, 0, 10, FCFS_END + 1).is_err());
+
+    let mut disabled = presale;
+    disabled.fcfs_enabled = false;
+    assert!(check_fcfs_purchase(&disabled, 0, 10, FCFS_START).is_err());
+}
+
+#[test]
+fn wallet_cap_counts_earlier_fcfs_purchases() {
+    let presale = fcfs_sale(1_000, 0, 100);
+
+    assert_eq!(check_fcfs_purchase(&presale, 0, 100, FCFS_START).unwrap(), 100);
+    assert_eq!(check_fcfs_purchase(&presale, 60, 40, FCFS_START).unwrap(), 100);
+    assert!(check_fcfs_purchase(&presale, 60, 41, FCFS_START).is_err());
+    assert!(check_fcfs_purchase(&presale, 0, 101, FCFS_START).is_err());
+}
+
+#[test]
+fn only_the_unsold_supply_is_available() {
+    // 950 of 1,000 tokens went in the guaranteed round
+    let presale = fcfs_sale(1_000, 950, 100);
+
+    assert_eq!(check_fcfs_purchase(&presale, 0, 50, FCFS_START).unwrap(), 50);
+    assert!(check_fcfs_purchase(&presale, 0, 51, FCFS_START).is_err());
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::presale::{
+    calculate_listing_amounts, validate_listing_price, validate_listing_terms,
+    validate_liquidity_reserve,
+};
+use protocol::state::{GlobalState, Presale};
+
+fn global_state(listing_band_min_bps: u16, listing_band_max_bps: u16) -> GlobalState {
+    let mut global_state =
+        zeroed::<GlobalState>();
+    global_state.listing_band_min_bps = listing_band_min_bps;
+    global_state.listing_band_max_bps = listing_band_max_bps;
+    global_state
+}
+
+#[test]
+fn listing_below_the_sale_price_is_rejected_by_default() {
+    let global_state = global_state(0, 0);
+
+    assert!(validate_listing_price(100, 99, &global_state).is_err());
+    assert!(validate_listing_price(100, 0, &global_state).is_err());
+    assert!(validate_listing_price(100, 100, &global_state).is_ok());
+    assert!(validate_listing_price(100, 10_000, &global_state).is_ok());
+}
+
+#[test]
+fn listing_price_must_sit_inside_the_band() {
+    // 90% to 150% of the sale price
+    let global_state = global_state(9_000, 15_000);
+
+    assert!(validate_listing_price(100, 89, &global_state).is_err());
+    assert!(validate_listing_price(100, 90, &global_state).is_ok());
+    assert!(validate_listing_price(100, 150, &global_state).is_ok());
+    assert!(validate_listing_price(100, 151, &global_state).is_err());
+}
+
+#[test]
+fn liquidity_reserve_must_pair_the_listed_share_of_the_raise() {
+    // 1,000 tokens at 10 lamports: 8,000 lamports go to the pool at listing,
+    // which pairs with 400 tokens at a listing price of 20
+    assert!(validate_liquidity_reserve(1_000, 10, 400, 20).is_ok());
+    assert!(validate_liquidity_reserve(1_000, 10, 399, 20).is_err());
+    assert!(validate_liquidity_reserve(1_000, 10, 0, 20).is_err());
+}
+
+fn listed_sale(tokens_for_liquidity: u64) -> Presale {
+    let mut presale = zeroed::<Presale>();
+    presale.tokens_for_sale = 1_000;
+    presale.tokens_for_liquidity = tokens_for_liquidity;
+    presale.listing_price = 20;
+    presale
+}
+
+#[test]
+fn listing_terms_are_checked_at_the_highest_sale_price() {
+    let global_state = global_state(0, 0);
+    let presale = listed_sale(400);
+
+    // A Dutch auction floored at 10 but opening at 30 can't list at 20
+    assert!(validate_listing_terms(&presale, 10, &global_state).is_ok());
+    assert!(validate_listing_terms(&presale, 30, &global_state).is_err());
+
+    // Nor can the reserve pair a raise at 20 per token
+    let mut presale = listed_sale(400);
+    presale.listing_price = 40;
+    assert!(validate_listing_terms(&presale, 30, &global_state).is_err());
+    presale.tokens_for_liquidity = 600;
+    assert!(validate_listing_terms(&presale, 30, &global_state).is_ok());
+}
+
+#[test]
+fn listing_pairs_the_raise_up_to_the_reserve() {
+    let mut presale = listed_sale(400);
+    presale.sol_raised = 10_000;
+    assert_eq!(calculate_listing_amounts(&presale), (8_000, 400));
+
+    // An auction that raised more still opens at the listing price
+    presale.sol_raised = 20_000;
+    assert_eq!(calculate_listing_amounts(&presale), (8_000, 400));
+}
+
+#[test]
+fn presales_without_a_reserve_list_uncapped() {
+    let mut presale = listed_sale(0);
+    presale.sol_raised = 20_000;
+
+    assert_eq!(calculate_listing_amounts(&presale), (16_000, 800));
+}
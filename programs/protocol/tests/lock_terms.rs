@@ -0,0 +1,54 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::constants::*;
+use protocol::staking::{add_to_position, check_unstake};
+use protocol::state::UserStake;
+
+fn position() -> UserStake {
+    zeroed::<UserStake>()
+}
+
+#[test]
+fn locked_positions_cannot_be_unstaked_before_the_lock_ends() {
+    let mut user_stake = position();
+    add_to_position(&mut user_stake, 1_000, LOCK_TERM_30_DAYS, 0).unwrap();
+
+    let lock_end = LOCK_TERM_30_DAYS_DURATION;
+    assert_eq!(user_stake.lock_end, lock_end);
+
+    // Not even a partial withdrawal while locked
+    assert!(check_unstake(&user_stake, 1, lock_end - 1).is_err());
+    assert!(check_unstake(&user_stake, 1_000, lock_end).is_ok());
+    assert!(check_unstake(&user_stake, 1_001, lock_end).is_err());
+}
+
+#[test]
+fn flexible_positions_can_be_unstaked_at_any_time() {
+    let mut user_stake = position();
+    add_to_position(&mut user_stake, 1_000, LOCK_TERM_FLEXIBLE, 100).unwrap();
+
+    assert!(check_unstake(&user_stake, 1_000, 100).is_ok());
+}
+
+#[test]
+fn top_ups_never_shorten_a_lock() {
+    let mut user_stake = position();
+    add_to_position(&mut user_stake, 1_000, LOCK_TERM_180_DAYS, 0).unwrap();
+
+    // A shorter or flexible top-up keeps the longer lock and its boost
+    add_to_position(&mut user_stake, 1_000, LOCK_TERM_30_DAYS, 10).unwrap();
+    add_to_position(&mut user_stake, 1_000, LOCK_TERM_FLEXIBLE, 20).unwrap();
+
+    assert_eq!(user_stake.lock_end, LOCK_TERM_180_DAYS_DURATION);
+    assert_eq!(user_stake.lock_weight_bps, LOCK_TERM_180_DAYS_WEIGHT_BPS);
+    assert!(check_unstake(&user_stake, 1, LOCK_TERM_180_DAYS_DURATION - 1).is_err());
+}
+
+#[test]
+fn unknown_lock_terms_are_rejected() {
+    let mut user_stake = position();
+
+    assert!(add_to_position(&mut user_stake, 1_000, 4, 0).is_err());
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::zeroed;
+use protocol::constants::*;
+use protocol::staking::add_to_position;
+use protocol::state::UserStake;
+use protocol::tier::{get_effective_stake, get_loyalty_multiplier_bps};
+
+fn flexible_position(amount: u64, staked_at: i64) -> UserStake {
+    let mut user_stake = zeroed::<UserStake>();
+    add_to_position(&mut user_stake, amount, LOCK_TERM_FLEXIBLE, staked_at).unwrap();
+    user_stake.power_amount = amount;
+    user_stake.mint_weight_bps = BPS_DENOMINATOR as u16;
+    user_stake
+}
+
+#[test]
+fn multiplier_ramps_up_with_holding_time() {
+    let ramp = [
+        (0, LOYALTY_FLASH_STAKE_MULTIPLIER_BPS),
+        (LOYALTY_FLASH_STAKE_DURATION - 1, LOYALTY_FLASH_STAKE_MULTIPLIER_BPS),
+        (LOYALTY_FLASH_STAKE_DURATION, LOYALTY_BASE_MULTIPLIER_BPS),
+        (LOYALTY_SHORT_TERM_DURATION, LOYALTY_SHORT_TERM_MULTIPLIER_BPS),
+        (LOYALTY_MEDIUM_TERM_DURATION, LOYALTY_MEDIUM_TERM_MULTIPLIER_BPS),
+        (LOYALTY_LONG_TERM_DURATION, LOYALTY_LONG_TERM_MULTIPLIER_BPS),
+        (i64::MAX, LOYALTY_LONG_TERM_MULTIPLIER_BPS),
+    ];
+
+    for (duration, multiplier) in ramp {
+        assert_eq!(get_loyalty_multiplier_bps(duration), multiplier);
+    }
+}
+
+#[test]
+fn flash_stakes_count_for_half() {
+    let user_stake = flexible_position(10_000, 1_000);
+
+    assert_eq!(get_effective_stake(&user_stake, 1_000), 5_000);
+    assert_eq!(
+        get_effective_stake(&user_stake, 1_000 + LOYALTY_FLASH_STAKE_DURATION),
+        10_000
+    );
+    assert_eq!(
+        get_effective_stake(&user_stake, 1_000 + LOYALTY_LONG_TERM_DURATION),
+        20_000
+    );
+}
+
+#[test]
+fn top_ups_average_into_the_holding_time() {
+    let mut user_stake = flexible_position(10_000, 0);
+
+    // Doubling the stake halfway through keeps half the holding time
+    let now = LOYALTY_MEDIUM_TERM_DURATION;
+    add_to_position(&mut user_stake, 10_000, LOCK_TERM_FLEXIBLE, now).unwrap();
+    user_stake.power_amount = user_stake.amount;
+
+    assert_eq!(user_stake.lock_time, now / 2);
+    assert_eq!(
+        get_loyalty_multiplier_bps(now - user_stake.lock_time),
+        LOYALTY_SHORT_TERM_MULTIPLIER_BPS
+    );
+    assert_eq!(get_effective_stake(&user_stake, now), 25_000);
+}
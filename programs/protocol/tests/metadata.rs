@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use protocol::constants::*;
+use protocol::metadata::set_presale_metadata;
+use protocol::state::{PresaleMetadata, PresaleMetadataArgs};
+
+fn metadata() -> PresaleMetadata {
+    PresaleMetadata {
+        presale: Pubkey::new_unique(),
+        name: "Old".to_string(),
+        symbol: "OLD".to_string(),
+        uri: String::new(),
+        content_hash: [0; 32],
+        bump: 0,
+    }
+}
+
+fn args(name_len: usize, symbol_len: usize, uri_len: usize) -> PresaleMetadataArgs {
+    PresaleMetadataArgs {
+        name: "n".repeat(name_len),
+        symbol: "s".repeat(symbol_len),
+        uri: "u".repeat(uri_len),
+        content_hash: [7; 32],
+    }
+}
+
+#[test]
+fn metadata_is_replaced_in_full() {
+    let mut metadata = metadata();
+    set_presale_metadata(&mut metadata, args(3, 2, 10)).unwrap();
+
+    assert_eq!(metadata.name, "nnn");
+    assert_eq!(metadata.symbol, "ss");
+    assert_eq!(metadata.uri, "u".repeat(10));
+    assert_eq!(metadata.content_hash, [7; 32]);
+}
+
+#[test]
+fn oversized_metadata_is_rejected_untouched() {
+    let limits = (METADATA_MAX_NAME_LEN, METADATA_MAX_SYMBOL_LEN, METADATA_MAX_URI_LEN);
+    let mut metadata = metadata();
+
+    for oversized in [
+        args(limits.0 + 1, 1, 1),
+        args(1, limits.1 + 1, 1),
+        args(1, 1, limits.2 + 1),
+    ] {
+        assert!(set_presale_metadata(&mut metadata, oversized).is_err());
+        assert_eq!(metadata.name, "Old");
+    }
+
+    assert!(set_presale_metadata(&mut metadata, args(limits.0, limits.1, limits.2)).is_ok());
+}
@@ -0,0 +1,397 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use common::zeroed;
+use protocol::constants::{ACCOUNT_VERSION, SEED_PREFIX_PRESALE, SEED_PREFIX_USER_STAKE};
+use protocol::migration::{upgrade_account_data, Versioned};
+use protocol::state::*;
+
+// Layouts as deployed before versioning, copied from the original state.rs
+#[derive(AnchorSerialize)]
+struct LegacyPresale {
+    admin: Pubkey,
+    creator: Pubkey,
+    mint_of_token_being_sold: Pubkey,
+    status: u8,
+    token_price: u64,
+    tokens_for_sale: u64,
+    tokens_sold: u64,
+    start_time: i64,
+    end_time: i64,
+    registration_start_time: i64,
+    registration_end_time: i64,
+    tier1_allocation: u64,
+    tier2_allocation: u64,
+    tier3_allocation: u64,
+    tier1_sold: u64,
+    tier2_sold: u64,
+    tier3_sold: u64,
+    sol_raised: u64,
+    listing_price: u64,
+    is_listed: bool,
+    presale_token_account: Pubkey,
+    presale_sol_account: Pubkey,
+    vesting_enabled: bool,
+    first_release_time: i64,
+    second_release_time: i64,
+    third_release_time: i64,
+    bump: u8,
+}
+
+#[derive(AnchorSerialize)]
+struct LegacyUserStake {
+    user: Pubkey,
+    staking_token_mint: Pubkey,
+    amount: u64,
+    lock_time: i64,
+    tier: u8,
+    bump: u8,
+}
+
+#[derive(AnchorSerialize)]
+struct LegacyUserPresaleInfo {
+    user: Pubkey,
+    presale: Pubkey,
+    allocation: u64,
+    purchased: u64,
+    claimed: u64,
+    first_claim_processed: bool,
+    second_claim_processed: bool,
+    third_claim_processed: bool,
+    bump: u8,
+}
+
+#[derive(AnchorSerialize)]
+struct LegacyGlobalState {
+    admin: Pubkey,
+    staking_token_mint: Pubkey,
+    treasury_wallet: Pubkey,
+    total_presales: u64,
+    active_presales: u64,
+    total_stakers: u64,
+    bump: u8,
+}
+
+/// Account data as the old program wrote it, then zero-extended the way the
+/// migration realloc does.
+fn legacy_account_data<L: AnchorSerialize, T: Versioned>(
+    discriminator: &[u8],
+    legacy: &L,
+) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+    legacy.serialize(&mut data).unwrap();
+    data.resize(T::SPACE, 0);
+    data
+}
+
+fn legacy_presale() -> LegacyPresale {
+    LegacyPresale {
+        admin: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        mint_of_token_being_sold: Pubkey::new_unique(),
+        status: 3,
+        token_price: 1_000,
+        tokens_for_sale: 90_000,
+        tokens_sold: 60_000,
+        start_time: 1_700_000_000,
+        end_time: 1_700_086_400,
+        registration_start_time: 1_699_900_000,
+        registration_end_time: 1_699_990_000,
+        tier1_allocation: 30_600,
+        tier2_allocation: 29_700,
+        tier3_allocation: 29_700,
+        tier1_sold: 30_600,
+        tier2_sold: 20_000,
+        tier3_sold: 9_400,
+        sol_raised: 60_000_000,
+        listing_price: 1_200,
+        is_listed: true,
+        presale_token_account: Pubkey::new_unique(),
+        presale_sol_account: Pubkey::new_unique(),
+        vesting_enabled: true,
+        first_release_time: 1_700_086_400,
+        second_release_time: 1_702_678_400,
+        third_release_time: 1_705_270_400,
+        bump: 252,
+    }
+}
+
+fn legacy_user_stake() -> LegacyUserStake {
+    LegacyUserStake {
+        user: Pubkey::new_unique(),
+        staking_token_mint: Pubkey::new_unique(),
+        amount: 5_000,
+        lock_time: 1_700_000_000,
+        tier: 2,
+        bump: 254,
+    }
+}
+
+#[test]
+fn legacy_user_stake_deserializes_as_version_zero() {
+    let legacy = legacy_user_stake();
+    let data = legacy_account_data::<_, UserStake>(UserStake::DISCRIMINATOR, &legacy);
+
+    let stake = UserStake::try_deserialize(&mut &data[..]).unwrap();
+
+    assert_eq!(stake.user, legacy.user);
+    assert_eq!(stake.staking_token_mint, legacy.staking_token_mint);
+    assert_eq!(stake.amount, legacy.amount);
+    assert_eq!(stake.lock_time, legacy.lock_time);
+    assert_eq!(stake.legacy_tier, legacy.tier);
+    assert_eq!(stake.bump, legacy.bump);
+    assert_eq!(stake.version, 0);
+    assert_eq!(stake.reserved, [0; 64]);
+}
+
+#[test]
+fn legacy_presale_deserializes_as_version_zero() {
+    let legacy = legacy_presale();
+    let data = legacy_account_data::<_, Presale>(Presale::DISCRIMINATOR, &legacy);
+
+    let presale = Presale::try_deserialize(&mut &data[..]).unwrap();
+
+    assert_eq!(presale.creator, legacy.creator);
+    assert_eq!(presale.mint_of_token_being_sold, legacy.mint_of_token_being_sold);
+    assert_eq!(presale.status, legacy.status);
+    assert_eq!(presale.tokens_sold, legacy.tokens_sold);
+    assert_eq!(presale.tier3_sold, legacy.tier3_sold);
+    assert_eq!(presale.sol_raised, legacy.sol_raised);
+    assert_eq!(presale.presale_token_account, legacy.presale_token_account);
+    assert_eq!(presale.third_release_time, legacy.third_release_time);
+    assert_eq!(presale.bump, legacy.bump);
+    assert_eq!(presale.version, 0);
+}
+
+#[test]
+fn presale_migrates_to_current_version() {
+    let legacy = legacy_presale();
+    let mut data = legacy_account_data::<_, Presale>(Presale::DISCRIMINATOR, &legacy);
+
+    upgrade_account_data::<Presale>(&mut data).unwrap();
+
+    let presale = Presale::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(presale.version, ACCOUNT_VERSION);
+    assert_eq!(presale.admin, legacy.admin);
+    assert_eq!(presale.listing_price, legacy.listing_price);
+    assert!(presale.is_listed);
+    assert_eq!(presale.second_release_time, legacy.second_release_time);
+    assert_eq!(presale.bump, legacy.bump);
+    assert_eq!(presale.migration_owner(), legacy.creator);
+}
+
+#[test]
+fn version_one_presale_migrates_without_a_liquidity_reserve() {
+    let mut presale = zeroed::<Presale>();
+    presale.version = 1;
+    presale.presale_id = 7;
+    presale.tokens_for_sale = 90_000;
+    presale.vesting_percentages = [50, 25, 25];
+    presale.open_settlements = 3;
+
+    // Version 1 ended with open_settlements
+    let mut data = Vec::new();
+    presale.try_serialize(&mut data).unwrap();
+    data.truncate(Presale::SPACE - 8 - 64);
+    data.resize(Presale::SPACE, 0);
+
+    let migrated = upgrade_account_data::<Presale>(&mut data).unwrap();
+
+    assert_eq!(migrated.version, ACCOUNT_VERSION);
+    assert_eq!(migrated.presale_id, 7);
+    assert_eq!(migrated.tokens_for_sale, 90_000);
+    assert_eq!(migrated.vesting_percentages, [50, 25, 25]);
+    assert_eq!(migrated.open_settlements, 3);
+    assert_eq!(migrated.tokens_for_liquidity, 0);
+    assert!(!migrated.legacy_seeds);
+}
+
+#[test]
+fn presale_migration_keeps_the_fixed_vesting_schedule() {
+    let mut data = legacy_account_data::<_, Presale>(Presale::DISCRIMINATOR, &legacy_presale());
+
+    let presale = upgrade_account_data::<Presale>(&mut data).unwrap();
+
+    assert_eq!(presale.vesting_percentages, [40, 30, 30]);
+}
+
+#[test]
+fn migrated_presale_keeps_its_original_address() {
+    let legacy = legacy_presale();
+    let (address, bump) = Pubkey::find_program_address(
+        &[
+            SEED_PREFIX_PRESALE,
+            legacy.mint_of_token_being_sold.as_ref(),
+            legacy.creator.as_ref(),
+        ],
+        &protocol::ID,
+    );
+    let mut data = legacy_account_data::<_, Presale>(
+        Presale::DISCRIMINATOR,
+        &LegacyPresale { bump, ..legacy },
+    );
+
+    let presale = upgrade_account_data::<Presale>(&mut data).unwrap();
+
+    assert!(presale.legacy_seeds);
+    let derived = Pubkey::create_program_address(
+        &[
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+            &[presale.bump],
+        ],
+        &protocol::ID,
+    )
+    .unwrap();
+    assert_eq!(derived, address);
+}
+
+#[test]
+fn user_stake_migrates_to_current_version() {
+    let legacy = legacy_user_stake();
+    let mut data = legacy_account_data::<_, UserStake>(UserStake::DISCRIMINATOR, &legacy);
+
+    let migrated = upgrade_account_data::<UserStake>(&mut data).unwrap();
+    assert_eq!(migrated.version, ACCOUNT_VERSION);
+
+    // The upgrade is written back and leaves the existing fields untouched
+    let stake = UserStake::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(stake.version, ACCOUNT_VERSION);
+    assert_eq!(stake.user, legacy.user);
+    assert_eq!(stake.amount, legacy.amount);
+    assert_eq!(stake.lock_time, legacy.lock_time);
+    assert_eq!(stake.bump, legacy.bump);
+    assert_eq!(stake.migration_owner(), legacy.user);
+}
+
+#[test]
+fn user_stake_migration_fills_non_zero_defaults() {
+    let legacy = legacy_user_stake();
+    let mut data = legacy_account_data::<_, UserStake>(UserStake::DISCRIMINATOR, &legacy);
+
+    let stake = upgrade_account_data::<UserStake>(&mut data).unwrap();
+
+    assert_eq!(stake.lock_end, 0);
+    assert_eq!(stake.lock_weight_bps, 10_000);
+    assert_eq!(stake.mint_weight_bps, 10_000);
+    assert_eq!(stake.power_amount, legacy.amount);
+    assert_eq!(stake.rent_payer, legacy.user);
+}
+
+#[test]
+fn migrated_user_stake_keeps_its_original_address() {
+    let legacy = legacy_user_stake();
+    let (address, bump) = Pubkey::find_program_address(
+        &[
+            SEED_PREFIX_USER_STAKE,
+            legacy.user.as_ref(),
+            legacy.staking_token_mint.as_ref(),
+        ],
+        &protocol::ID,
+    );
+    let mut data = legacy_account_data::<_, UserStake>(
+        UserStake::DISCRIMINATOR,
+        &LegacyUserStake { bump, ..legacy },
+    );
+
+    let stake = upgrade_account_data::<UserStake>(&mut data).unwrap();
+
+    assert!(stake.legacy_seeds);
+    let derived = Pubkey::create_program_address(
+        &[
+            SEED_PREFIX_USER_STAKE,
+            stake.user.as_ref(),
+            stake.staking_token_mint.as_ref(),
+            stake.index_seed().as_ref(),
+            &[stake.bump],
+        ],
+        &protocol::ID,
+    )
+    .unwrap();
+    assert_eq!(derived, address);
+}
+
+#[test]
+fn user_presale_info_migrates_to_current_version() {
+    let legacy = LegacyUserPresaleInfo {
+        user: Pubkey::new_unique(),
+        presale: Pubkey::new_unique(),
+        allocation: 1_000,
+        purchased: 800,
+        claimed: 200,
+        first_claim_processed: true,
+        second_claim_processed: false,
+        third_claim_processed: false,
+        bump: 253,
+    };
+    let mut data = legacy_account_data::<_, UserPresaleInfo>(
+        UserPresaleInfo::DISCRIMINATOR,
+        &legacy,
+    );
+
+    upgrade_account_data::<UserPresaleInfo>(&mut data).unwrap();
+
+    let user_info = UserPresaleInfo::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(user_info.version, ACCOUNT_VERSION);
+    assert_eq!(user_info.presale, legacy.presale);
+    assert_eq!(user_info.allocation, legacy.allocation);
+    assert_eq!(user_info.purchased, legacy.purchased);
+    assert_eq!(user_info.claimed, legacy.claimed);
+    assert!(user_info.first_claim_processed);
+    assert!(!user_info.second_claim_processed);
+    assert_eq!(user_info.bump, legacy.bump);
+    assert_eq!(user_info.rent_payer, legacy.user);
+}
+
+#[test]
+fn global_state_migrates_to_current_version() {
+    let legacy = LegacyGlobalState {
+        admin: Pubkey::new_unique(),
+        staking_token_mint: Pubkey::new_unique(),
+        treasury_wallet: Pubkey::new_unique(),
+        total_presales: 12,
+        active_presales: 3,
+        total_stakers: 40,
+        bump: 255,
+    };
+    let mut data = legacy_account_data::<_, GlobalState>(GlobalState::DISCRIMINATOR, &legacy);
+
+    upgrade_account_data::<GlobalState>(&mut data).unwrap();
+
+    let global_state = GlobalState::try_deserialize(&mut &data[..]).unwrap();
+    assert_eq!(global_state.version, ACCOUNT_VERSION);
+    assert_eq!(global_state.admin, legacy.admin);
+    assert_eq!(global_state.treasury_wallet, legacy.treasury_wallet);
+    assert_eq!(global_state.total_presales, legacy.total_presales);
+    assert_eq!(global_state.total_stakers, legacy.total_stakers);
+    assert_eq!(global_state.bump, legacy.bump);
+    assert_eq!(global_state.migration_owner(), legacy.admin);
+}
+
+#[test]
+fn migrated_account_cannot_be_migrated_again() {
+    let mut data = legacy_account_data::<_, UserStake>(UserStake::DISCRIMINATOR, &legacy_user_stake());
+
+    upgrade_account_data::<UserStake>(&mut data).unwrap();
+
+    assert!(upgrade_account_data::<UserStake>(&mut data).is_err());
+}
+
+#[test]
+fn migration_rejects_accounts_of_another_type() {
+    let mut data = legacy_account_data::<_, UserStake>(Presale::DISCRIMINATOR, &legacy_user_stake());
+
+    assert!(upgrade_account_data::<UserStake>(&mut data).is_err());
+}
+
+#[test]
+fn migration_requires_reallocated_account() {
+    let mut data = UserStake::DISCRIMINATOR.to_vec();
+    legacy_user_stake().serialize(&mut data).unwrap();
+
+    assert!(data.len() < UserStake::SPACE);
+    assert!(upgrade_account_data::<UserStake>(&mut data).is_err());
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::overflow::calculate_overflow_allocation;
+use protocol::state::Presale;
+
+fn overflow_sale(tokens_for_sale: u64, token_price: u64, total_committed: u64) -> Presale {
+    let mut presale = zeroed::<Presale>();
+    presale.tokens_for_sale = tokens_for_sale;
+    presale.token_price = token_price;
+    presale.total_committed = total_committed;
+    presale
+}
+
+#[test]
+fn oversubscribed_sale_is_split_pro_rata() {
+    // 4x oversubscribed: 1,000 tokens at 10 lamports, 40,000 lamports committed
+    let presale = overflow_sale(1_000, 10, 40_000);
+
+    assert_eq!(calculate_overflow_allocation(10_000, &presale), 250);
+    assert_eq!(calculate_overflow_allocation(30_000, &presale), 750);
+
+    // The refund is whatever the allocation didn't cost
+    let tokens = calculate_overflow_allocation(10_000, &presale);
+    assert_eq!(10_000 - tokens * presale.token_price, 7_500);
+}
+
+#[test]
+fn pro_rata_allocations_never_exceed_the_supply() {
+    let commitments = [3_333, 3_333, 3_334, 17, 9_983];
+    let presale = overflow_sale(1_000, 1, commitments.iter().sum());
+
+    let allocated: u64 = commitments
+        .iter()
+        .map(|&sol| calculate_overflow_allocation(sol, &presale))
+        .sum();
+
+    assert!(allocated <= presale.tokens_for_sale);
+}
+
+#[test]
+fn undersubscribed_sale_is_capped_at_the_token_price() {
+    // Half subscribed: pro-rata would give 1,000 tokens for 5,000 lamports
+    let presale = overflow_sale(1_000, 10, 5_000);
+
+    assert_eq!(calculate_overflow_allocation(5_000, &presale), 500);
+}
+
+#[test]
+fn empty_sale_allocates_nothing() {
+    let presale = overflow_sale(1_000, 10, 0);
+
+    assert_eq!(calculate_overflow_allocation(0, &presale), 0);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::constants::*;
+use protocol::penalty::is_penalizable_no_show;
+use protocol::state::{Presale, UserPresaleInfo};
+use protocol::tier::{calculate_presale_tier_allocations, has_unsold_tier_allocation};
+
+fn tiered_sale(tokens_for_sale: u64) -> Presale {
+    let mut presale = zeroed::<Presale>();
+    let (tier1, tier2, tier3) = calculate_presale_tier_allocations(tokens_for_sale);
+    presale.tokens_for_sale = tokens_for_sale;
+    presale.tier1_allocation = tier1;
+    presale.tier2_allocation = tier2;
+    presale.tier3_allocation = tier3;
+    presale
+}
+
+#[test]
+fn registrants_with_unsold_pools_are_no_shows() {
+    let presale = tiered_sale(1_000);
+
+    for tier in 1..=3 {
+        assert!(has_unsold_tier_allocation(tier, &presale));
+    }
+}
+
+#[test]
+fn sold_out_pools_excuse_the_registrant() {
+    let mut presale = tiered_sale(1_000);
+    presale.tier1_sold = presale.tier1_allocation;
+
+    // Tier 1 can only buy from its own pool
+    assert!(!has_unsold_tier_allocation(1, &presale));
+
+    // Higher tiers could still have bought from theirs
+    assert!(has_unsold_tier_allocation(2, &presale));
+
+    presale.tier2_sold = presale.tier2_allocation;
+    presale.tier3_sold = presale.tier3_allocation;
+    assert!(!has_unsold_tier_allocation(3, &presale));
+}
+
+#[test]
+fn unqualified_registrants_have_no_allocation() {
+    assert!(!has_unsold_tier_allocation(0, &tiered_sale(1_000)));
+}
+
+fn registrant(tier: u8) -> UserPresaleInfo {
+    let mut user_info = zeroed::<UserPresaleInfo>();
+    user_info.registered_tier = tier;
+    user_info
+}
+
+#[test]
+fn only_penalized_when_a_penalty_is_configured() {
+    let mut presale = tiered_sale(1_000);
+    assert!(!is_penalizable_no_show(&presale, &registrant(1)));
+
+    presale.no_show_cooldown = 86_400;
+    assert!(is_penalizable_no_show(&presale, &registrant(1)));
+}
+
+#[test]
+fn registrants_who_cannot_be_penalized_are_excused() {
+    let mut presale = tiered_sale(1_000);
+    presale.no_show_slash_bps = 500;
+
+    // Sold-out pools
+    presale.tier1_sold = presale.tier1_allocation;
+    assert!(!is_penalizable_no_show(&presale, &registrant(1)));
+
+    // Sale modes without allocations
+    for sale_mode in [SALE_MODE_OVERFLOW, SALE_MODE_BATCH_AUCTION, SALE_MODE_BONDING_CURVE] {
+        presale.sale_mode = sale_mode;
+        assert!(!is_penalizable_no_show(&presale, &registrant(2)));
+    }
+
+    presale.sale_mode = SALE_MODE_DUTCH_AUCTION;
+    assert!(is_penalizable_no_show(&presale, &registrant(2)));
+
+    // Buyers, committers and registrants already penalized
+    let mut user_info = registrant(2);
+    user_info.purchased = 1;
+    assert!(!is_penalizable_no_show(&presale, &user_info));
+
+    let mut user_info = registrant(2);
+    user_info.sol_committed = 1;
+    assert!(!is_penalizable_no_show(&presale, &user_info));
+
+    let mut user_info = registrant(2);
+    user_info.penalized = true;
+    assert!(!is_penalizable_no_show(&presale, &user_info));
+}
+
+#[test]
+fn allowlisted_registrants_are_penalized_without_a_tier() {
+    let mut presale = tiered_sale(1_000);
+    presale.no_show_cooldown = 86_400;
+
+    let mut user_info = registrant(0);
+    assert!(!is_penalizable_no_show(&presale, &user_info));
+
+    user_info.allocation = 100;
+    assert!(is_penalizable_no_show(&presale, &user_info));
+}
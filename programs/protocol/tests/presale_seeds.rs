@@ -0,0 +1,53 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::constants::*;
+use protocol::state::Presale;
+
+fn round(mint: Pubkey, creator: Pubkey, presale_id: u64) -> Presale {
+    let mut presale = zeroed::<Presale>();
+    presale.mint_of_token_being_sold = mint;
+    presale.creator = creator;
+    presale.presale_id = presale_id;
+    presale
+}
+
+fn presale_address(presale: &Presale) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            SEED_PREFIX_PRESALE,
+            presale.mint_of_token_being_sold.as_ref(),
+            presale.creator.as_ref(),
+            presale.id_seed().as_ref(),
+        ],
+        &protocol::ID,
+    )
+    .0
+}
+
+#[test]
+fn rounds_for_the_same_mint_and_creator_get_distinct_addresses() {
+    let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let first = presale_address(&round(mint, creator, 0));
+    let second = presale_address(&round(mint, creator, 1));
+
+    assert_ne!(first, second);
+    assert_eq!(first, presale_address(&round(mint, creator, 0)));
+}
+
+#[test]
+fn legacy_presales_keep_the_id_less_address() {
+    let (mint, creator) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut legacy = round(mint, creator, 5);
+    legacy.legacy_seeds = true;
+
+    let (expected, _) = Pubkey::find_program_address(
+        &[SEED_PREFIX_PRESALE, mint.as_ref(), creator.as_ref()],
+        &protocol::ID,
+    );
+
+    assert_eq!(presale_address(&legacy), expected);
+    assert_ne!(presale_address(&legacy), presale_address(&round(mint, creator, 5)));
+}
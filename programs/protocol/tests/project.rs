@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use protocol::project::{allocate_project_supply, schedule_round};
+use protocol::state::Project;
+
+fn project() -> Project {
+    Project {
+        mint: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        round_count: 0,
+        bump: 0,
+        version: 0,
+        tokens_deposited: 0,
+        tokens_allocated: 0,
+        last_round_end_time: 0,
+        reserved: [0; 40],
+    }
+}
+
+#[test]
+fn rounds_follow_one_another() {
+    let mut project = project();
+
+    assert_eq!(schedule_round(&mut project, 100, 1_000).unwrap(), 0);
+    assert_eq!(schedule_round(&mut project, 1_001, 2_000).unwrap(), 1_000);
+
+    assert_eq!(project.round_count, 2);
+    assert_eq!(project.last_round_end_time, 2_000);
+}
+
+#[test]
+fn overlapping_rounds_are_rejected() {
+    let mut project = project();
+    schedule_round(&mut project, 100, 1_000).unwrap();
+
+    // Registration can't open before the previous round has ended
+    assert!(schedule_round(&mut project, 1_000, 3_000).is_err());
+    assert!(schedule_round(&mut project, 500, 3_000).is_err());
+
+    assert_eq!(project.round_count, 1);
+    assert_eq!(project.last_round_end_time, 1_000);
+}
+
+#[test]
+fn rounds_cannot_allocate_more_than_the_deposit() {
+    let mut project = project();
+    project.tokens_deposited = 1_000;
+
+    allocate_project_supply(&mut project, 600).unwrap();
+    assert!(allocate_project_supply(&mut project, 401).is_err());
+    allocate_project_supply(&mut project, 400).unwrap();
+
+    assert_eq!(project.tokens_allocated, 1_000);
+    assert!(allocate_project_supply(&mut project, 1).is_err());
+}
@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use protocol::constants::{REWARD_PRECISION, SOL_REWARD_DURATION};
+use protocol::rewards::{distribute_sol_rewards, get_pool_power, update_pool_rewards};
+use protocol::state::{StakingMintConfig, StakingPool};
+
+fn pool(total_staked: u64) -> StakingPool {
+    StakingPool {
+        staking_token_mint: Pubkey::new_unique(),
+        reward_mint: Pubkey::new_unique(),
+        reward_vault: Pubkey::new_unique(),
+        total_staked,
+        reward_rate: 0,
+        acc_reward_per_share: 0,
+        last_update_time: 0,
+        acc_sol_per_share: 0,
+        bump: 0,
+        sol_reward_rate: 0,
+        sol_reward_end_time: 0,
+        rewards_remaining: 0,
+    }
+}
+
+fn mint_config(weight_bps: u16, decimals: u8) -> StakingMintConfig {
+    StakingMintConfig {
+        mint: Pubkey::new_unique(),
+        weight_bps,
+        enabled: true,
+        is_lp_token: false,
+        lp_reserve_account: Pubkey::default(),
+        bump: 0,
+        lp_rate: 0,
+        lp_rate_updated_at: 0,
+        decimals,
+        native_decimals: 6,
+    }
+}
+
+// Lamports a single token of stake has earned so far
+fn earned_per_token(pool: &StakingPool) -> u128 {
+    pool.acc_sol_per_share / REWARD_PRECISION
+}
+
+#[test]
+fn fee_revenue_streams_over_the_reward_duration() {
+    let mut pool = pool(1_000);
+    let fee = 1_000 * SOL_REWARD_DURATION as u64;
+
+    update_pool_rewards(&mut pool, 100);
+    distribute_sol_rewards(&mut pool, fee, 100);
+
+    // Nothing is paid out at the moment of collection
+    update_pool_rewards(&mut pool, 100);
+    assert_eq!(pool.acc_sol_per_share, 0);
+
+    update_pool_rewards(&mut pool, 100 + SOL_REWARD_DURATION / 2);
+    assert_eq!(earned_per_token(&pool), (fee / 2 / 1_000) as u128);
+
+    // The stream stops once the whole fee is paid out
+    update_pool_rewards(&mut pool, 100 + SOL_REWARD_DURATION * 2);
+    assert_eq!(earned_per_token(&pool), (fee / 1_000) as u128);
+}
+
+#[test]
+fn new_revenue_extends_the_unpaid_stream() {
+    let mut pool = pool(1_000);
+    let fee = 1_000 * SOL_REWARD_DURATION as u64;
+
+    distribute_sol_rewards(&mut pool, fee, 0);
+    update_pool_rewards(&mut pool, SOL_REWARD_DURATION / 2);
+    distribute_sol_rewards(&mut pool, fee, SOL_REWARD_DURATION / 2);
+    update_pool_rewards(&mut pool, SOL_REWARD_DURATION * 3);
+
+    assert_eq!(earned_per_token(&pool), (2 * fee / 1_000) as u128);
+}
+
+#[test]
+fn pools_are_weighted_by_normalized_power() {
+    // Same token count in a 9-decimal mint at half weight
+    let native = get_pool_power(&pool(1_000_000), &mint_config(10_000, 6));
+    let foreign = get_pool_power(&pool(1_000_000_000), &mint_config(5_000, 9));
+
+    assert_eq!(native, 1_000_000);
+    assert_eq!(foreign, 500_000);
+
+    let mut disabled = mint_config(10_000, 6);
+    disabled.enabled = false;
+    assert_eq!(get_pool_power(&pool(1_000_000), &disabled), 0);
+}
+
+#[test]
+fn revenue_streamed_to_an_empty_pool_carries_over() {
+    let mut pool = pool(0);
+    let fee = 1_000 * SOL_REWARD_DURATION as u64;
+
+    distribute_sol_rewards(&mut pool, fee, 0);
+    update_pool_rewards(&mut pool, 1_000);
+
+    // The first staker arrives after the pool sat empty
+    pool.total_staked = 1_000;
+    update_pool_rewards(&mut pool, SOL_REWARD_DURATION * 2);
+
+    assert_eq!(earned_per_token(&pool), (fee / 1_000) as u128);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::constants::*;
+use protocol::state::{GlobalState, Presale, UserStakeSummary};
+use protocol::utils::{record_stake_change, set_presale_status};
+
+fn pending_presale(global_state: &mut GlobalState) -> Presale {
+    global_state.presales_by_status[STATUS_PENDING as usize] += 1;
+    zeroed::<Presale>()
+}
+
+fn summary(total_power: u64, tier: u8) -> UserStakeSummary {
+    UserStakeSummary {
+        user: Pubkey::new_unique(),
+        total_power,
+        effective_amount: total_power,
+        tier,
+        position_count: 1,
+        next_position_index: 1,
+        boost_expiry: i64::MAX,
+        delegate: Pubkey::default(),
+        tier_cooldown_until: 0,
+        bump: 0,
+    }
+}
+
+#[test]
+fn status_counters_follow_each_transition() {
+    let mut global_state = zeroed::<GlobalState>();
+    let mut presale = pending_presale(&mut global_state);
+
+    let transitions = [
+        (STATUS_APPROVED, [0, 1, 0, 0, 0], 1),
+        (STATUS_LIVE, [0, 0, 1, 0, 0], 1),
+        (STATUS_COMPLETED, [0, 0, 0, 1, 0], 0),
+    ];
+
+    for (status, by_status, active) in transitions {
+        set_presale_status(&mut presale, &mut global_state, status);
+
+        assert_eq!(presale.status, status);
+        assert_eq!(global_state.presales_by_status, by_status);
+        assert_eq!(global_state.active_presales, active);
+    }
+}
+
+#[test]
+fn rejected_presales_move_from_pending_to_cancelled() {
+    let mut global_state = zeroed::<GlobalState>();
+    let mut presale = pending_presale(&mut global_state);
+    pending_presale(&mut global_state);
+
+    set_presale_status(&mut presale, &mut global_state, STATUS_CANCELLED);
+
+    assert_eq!(global_state.presales_by_status, [1, 0, 0, 0, 1]);
+    assert_eq!(global_state.active_presales, 0);
+}
+
+#[test]
+fn stake_changes_move_stakers_between_tiers() {
+    let mut global_state = zeroed::<GlobalState>();
+
+    // First stake
+    let staker = summary(TIER_1_REQUIREMENT, 1);
+    record_stake_change(&mut global_state, 0, 0, &staker);
+    assert_eq!(global_state.total_stakers, 1);
+    assert_eq!(global_state.total_staked, TIER_1_REQUIREMENT);
+    assert_eq!(global_state.tier_stakers, [0, 1, 0, 0]);
+
+    // Top-up into tier 2
+    let upgraded = summary(TIER_2_REQUIREMENT, 2);
+    record_stake_change(&mut global_state, staker.total_power, staker.tier, &upgraded);
+    assert_eq!(global_state.total_stakers, 1);
+    assert_eq!(global_state.total_staked, TIER_2_REQUIREMENT);
+    assert_eq!(global_state.tier_stakers, [0, 0, 1, 0]);
+
+    // Full unstake
+    let exited = summary(0, 0);
+    record_stake_change(&mut global_state, upgraded.total_power, upgraded.tier, &exited);
+    assert_eq!(global_state.total_stakers, 0);
+    assert_eq!(global_state.total_staked, 0);
+    assert_eq!(global_state.tier_stakers, [0, 0, 0, 0]);
+}
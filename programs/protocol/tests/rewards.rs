@@ -0,0 +1,110 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::rewards::{reset_reward_debt, settle_user_rewards, update_pool_rewards};
+use protocol::state::{StakingPool, UserStake};
+
+fn pool(reward_rate: u64) -> StakingPool {
+    StakingPool {
+        staking_token_mint: Pubkey::new_unique(),
+        reward_mint: Pubkey::new_unique(),
+        reward_vault: Pubkey::new_unique(),
+        total_staked: 0,
+        reward_rate,
+        acc_reward_per_share: 0,
+        last_update_time: 0,
+        acc_sol_per_share: 0,
+        bump: 0,
+        sol_reward_rate: 0,
+        sol_reward_end_time: 0,
+        rewards_remaining: u64::MAX,
+    }
+}
+
+fn position() -> UserStake {
+    zeroed::<UserStake>()
+}
+
+// Same order of operations as stake and unstake
+fn change_stake(pool: &mut StakingPool, user_stake: &mut UserStake, amount: i64, current_time: i64) {
+    update_pool_rewards(pool, current_time);
+    settle_user_rewards(pool, user_stake);
+
+    user_stake.amount = user_stake.amount.checked_add_signed(amount).unwrap();
+    pool.total_staked = pool.total_staked.checked_add_signed(amount).unwrap();
+
+    reset_reward_debt(pool, user_stake);
+}
+
+#[test]
+fn rewards_are_split_by_stake_share() {
+    // 300 reward tokens per second
+    let mut pool = pool(300);
+    let mut alice = position();
+    let mut bob = position();
+
+    change_stake(&mut pool, &mut alice, 300, 0);
+    change_stake(&mut pool, &mut bob, 100, 10);
+
+    // Settle both at t = 20
+    change_stake(&mut pool, &mut alice, 0, 20);
+    change_stake(&mut pool, &mut bob, 0, 20);
+
+    // Alice had the pool to herself for 10s, then three quarters of it
+    assert_eq!(alice.pending_rewards, 3_000 + 2_250);
+    assert_eq!(bob.pending_rewards, 750);
+}
+
+#[test]
+fn late_stakers_do_not_earn_past_rewards() {
+    let mut pool = pool(100);
+    let mut early = position();
+    let mut late = position();
+
+    change_stake(&mut pool, &mut early, 100, 0);
+    change_stake(&mut pool, &mut late, 100, 50);
+    change_stake(&mut pool, &mut late, 0, 50);
+
+    assert_eq!(late.pending_rewards, 0);
+}
+
+#[test]
+fn unstaked_tokens_stop_earning() {
+    let mut pool = pool(100);
+    let mut user_stake = position();
+
+    change_stake(&mut pool, &mut user_stake, 100, 0);
+    change_stake(&mut pool, &mut user_stake, -100, 10);
+    change_stake(&mut pool, &mut user_stake, 0, 30);
+
+    assert_eq!(user_stake.pending_rewards, 1_000);
+}
+
+#[test]
+fn empty_pool_accrues_nothing() {
+    let mut pool = pool(100);
+
+    update_pool_rewards(&mut pool, 1_000);
+
+    assert_eq!(pool.acc_reward_per_share, 0);
+    assert_eq!(pool.last_update_time, 1_000);
+}
+
+#[test]
+fn emissions_stop_when_the_funded_rewards_run_out() {
+    let mut pool = pool(100);
+    pool.rewards_remaining = 1_500;
+    let mut user_stake = position();
+
+    change_stake(&mut pool, &mut user_stake, 100, 0);
+    change_stake(&mut pool, &mut user_stake, 0, 30);
+
+    // 3,000 would have been emitted, but only 1,500 was funded
+    assert_eq!(user_stake.pending_rewards, 1_500);
+    assert_eq!(pool.rewards_remaining, 0);
+
+    // Nothing more accrues until the vault is topped up
+    change_stake(&mut pool, &mut user_stake, 0, 60);
+    assert_eq!(user_stake.pending_rewards, 1_500);
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::constants::*;
+use protocol::staking::{add_to_position, apply_position_to_summary};
+use protocol::state::{UserStake, UserStakeSummary};
+use protocol::tier::get_summary_tier;
+
+fn empty_summary() -> UserStakeSummary {
+    UserStakeSummary {
+        user: Pubkey::new_unique(),
+        total_power: 0,
+        effective_amount: 0,
+        tier: 0,
+        position_count: 0,
+        next_position_index: 0,
+        boost_expiry: i64::MAX,
+        delegate: Pubkey::default(),
+        tier_cooldown_until: 0,
+        bump: 0,
+    }
+}
+
+fn stake(summary: &mut UserStakeSummary, amount: u64, lock_term: u8) -> UserStake {
+    let mut user_stake = zeroed::<UserStake>();
+    add_to_position(&mut user_stake, amount, lock_term, 0).unwrap();
+    user_stake.power_amount = amount;
+    user_stake.mint_weight_bps = BPS_DENOMINATOR as u16;
+
+    apply_position_to_summary(summary, &mut user_stake, 0, 0);
+    user_stake
+}
+
+#[test]
+fn summaries_without_a_lock_boost_never_go_stale() {
+    let mut summary = empty_summary();
+    stake(&mut summary, 10 * TIER_3_REQUIREMENT, LOCK_TERM_FLEXIBLE);
+
+    assert_eq!(summary.boost_expiry, i64::MAX);
+    assert!(get_summary_tier(&summary, LOCK_TERM_180_DAYS_DURATION).is_ok());
+}
+
+#[test]
+fn stale_summary_is_rejected_at_registration() {
+    let mut summary = empty_summary();
+    let mut user_stake = stake(&mut summary, 10 * TIER_3_REQUIREMENT, LOCK_TERM_30_DAYS);
+    let lock_end = LOCK_TERM_30_DAYS_DURATION;
+
+    assert_eq!(summary.boost_expiry, lock_end);
+    assert!(get_summary_tier(&summary, lock_end - 1).is_ok());
+    assert!(get_summary_tier(&summary, lock_end).is_err());
+
+    // A refresh revalues the position without the boost
+    summary.total_power = 0;
+    summary.effective_amount = 0;
+    summary.boost_expiry = i64::MAX;
+    user_stake.effective_amount = 0;
+    apply_position_to_summary(&mut summary, &mut user_stake, 0, lock_end);
+
+    assert!(get_summary_tier(&summary, lock_end).is_ok());
+}
+
+#[test]
+fn cooldown_blocks_registration_until_it_ends() {
+    let mut summary = empty_summary();
+    stake(&mut summary, 10 * TIER_3_REQUIREMENT, LOCK_TERM_FLEXIBLE);
+    summary.tier_cooldown_until = 1_000;
+
+    assert!(get_summary_tier(&summary, 999).is_err());
+    assert!(get_summary_tier(&summary, 1_000).is_ok());
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::zeroed;
+use protocol::staking::get_position_power_amount;
+use protocol::state::{StakingMintConfig, UserStake};
+use protocol::tier::{get_weighted_power, normalize_to_native_decimals};
+
+fn position(amount: u64) -> UserStake {
+    let mut stake = zeroed::<UserStake>();
+    stake.amount = amount;
+    stake
+}
+
+fn mint_config(decimals: u8, native_decimals: u8) -> StakingMintConfig {
+    StakingMintConfig {
+        mint: Pubkey::new_unique(),
+        weight_bps: 10_000,
+        enabled: true,
+        is_lp_token: false,
+        lp_reserve_account: Pubkey::default(),
+        bump: 0,
+        lp_rate: 0,
+        lp_rate_updated_at: 0,
+        decimals,
+        native_decimals,
+    }
+}
+
+#[test]
+fn amounts_are_converted_to_native_decimals() {
+    assert_eq!(normalize_to_native_decimals(5_000_000_000, 9, 6), 5_000_000);
+    assert_eq!(normalize_to_native_decimals(5_000, 3, 6), 5_000_000);
+    assert_eq!(normalize_to_native_decimals(5_000_000, 6, 6), 5_000_000);
+}
+
+#[test]
+fn one_token_of_any_mint_has_the_same_power() {
+    // 1 token of a 9-decimal mint and 1 token of the 6-decimal native mint
+    let foreign = get_position_power_amount(&position(1_000_000_000), &mint_config(9, 6));
+    let native = get_position_power_amount(&position(1_000_000), &mint_config(6, 6));
+
+    assert_eq!(foreign, native);
+}
+
+#[test]
+fn weighted_power_applies_the_mint_weight() {
+    let mut stake = position(1_000_000);
+    stake.power_amount = 1_000_000;
+    stake.mint_weight_bps = 5_000;
+
+    assert_eq!(get_weighted_power(&stake), 500_000);
+
+    // Disabled mints carry no weight
+    stake.mint_weight_bps = 0;
+    assert_eq!(get_weighted_power(&stake), 0);
+}
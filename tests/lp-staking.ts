@@ -0,0 +1,303 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { createAccount, createMint, mintTo } from "@solana/spl-token";
+import { assert } from "chai";
+import { Protocol } from "../target/types/protocol";
+
+describe("lp staking", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Protocol as Program<Protocol>;
+  const connection = provider.connection;
+  const admin = (provider.wallet as anchor.Wallet).payer;
+
+  const user = Keypair.generate();
+  // Stands in for the AMM pool that owns the reserves and holds the rest of the LP supply
+  const pool = Keypair.generate();
+
+  const LP_SUPPLY = 500_000;
+  const NATIVE_RESERVE = 1_000_000;
+  const USER_LP = 200_000;
+  const STAKED_LP = 100_000;
+
+  const [globalState] = PublicKey.findProgramAddressSync(
+    [Buffer.from("global_state")],
+    program.programId
+  );
+
+  let nativeMint: PublicKey;
+  let lpMint: PublicKey;
+  let poolReserve: PublicKey;
+  let userLpAccount: PublicKey;
+
+  // Native tokens per LP token at listing, scaled like the on-chain rate
+  const LP_RATE_PRECISION = 1_000_000_000_000;
+  const initialRate = (NATIVE_RESERVE * LP_RATE_PRECISION) / LP_SUPPLY;
+
+  const positionIndex = (index: number) => {
+    const buf = Buffer.alloc(4);
+    buf.writeUInt32LE(index);
+    return buf;
+  };
+
+  const pda = (seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+  const userStake = () =>
+    pda([
+      Buffer.from("user_stake"),
+      user.publicKey.toBuffer(),
+      lpMint.toBuffer(),
+      positionIndex(0),
+    ]);
+
+  const stakeAccounts = () => ({
+    user: user.publicKey,
+    userStake: userStake(),
+    userStakeSummary: pda([
+      Buffer.from("stake_summary"),
+      user.publicKey.toBuffer(),
+    ]),
+    userTokenAccount: userLpAccount,
+    stakeTokenAccount: pda([
+      Buffer.from("user_stake"),
+      user.publicKey.toBuffer(),
+      lpMint.toBuffer(),
+      positionIndex(0),
+      Buffer.from("token_account"),
+    ]),
+    stakingTokenMint: lpMint,
+    stakingMintConfig: pda([Buffer.from("staking_mint"), lpMint.toBuffer()]),
+    stakingPool: pda([Buffer.from("staking_pool"), lpMint.toBuffer()]),
+    globalState,
+  });
+
+  before(async () => {
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(user.publicKey, 10 * LAMPORTS_PER_SOL)
+    );
+
+    const existing = await program.account.globalState.fetchNullable(
+      globalState
+    );
+
+    if (existing) {
+      nativeMint = existing.stakingTokenMint;
+    } else {
+      nativeMint = await createMint(connection, admin, admin.publicKey, null, 6);
+      await program.methods
+        .initialize(nativeMint, admin.publicKey)
+        .accountsPartial({ admin: admin.publicKey, globalState })
+        .rpc();
+    }
+
+    // Locally created pool: native token reserve plus an LP mint
+    lpMint = await createMint(connection, admin, admin.publicKey, null, 6);
+    poolReserve = await createAccount(
+      connection,
+      admin,
+      nativeMint,
+      pool.publicKey
+    );
+    await mintTo(connection, admin, nativeMint, poolReserve, admin, NATIVE_RESERVE);
+
+    userLpAccount = await createAccount(connection, admin, lpMint, user.publicKey);
+    const poolLpAccount = await createAccount(
+      connection,
+      admin,
+      lpMint,
+      pool.publicKey
+    );
+    await mintTo(connection, admin, lpMint, userLpAccount, admin, USER_LP);
+    await mintTo(connection, admin, lpMint, poolLpAccount, admin, LP_SUPPLY - USER_LP);
+
+    await program.methods
+      .addStakingMint(10_000)
+      .accountsPartial({
+        admin: admin.publicKey,
+        stakingMintConfig: pda([Buffer.from("staking_mint"), lpMint.toBuffer()]),
+        stakingTokenMint: lpMint,
+        nativeTokenMint: nativeMint,
+        lpReserveAccount: poolReserve,
+        globalState,
+      })
+      .rpc();
+
+    await program.methods
+      .initializeStakingPool(new BN(0))
+      .accountsPartial({
+        admin: admin.publicKey,
+        stakingPool: pda([Buffer.from("staking_pool"), lpMint.toBuffer()]),
+        rewardVault: pda([
+          Buffer.from("staking_pool"),
+          lpMint.toBuffer(),
+          Buffer.from("reward_vault"),
+        ]),
+        stakingTokenMint: lpMint,
+        stakingMintConfig: pda([Buffer.from("staking_mint"), lpMint.toBuffer()]),
+        rewardMint: nativeMint,
+        globalState,
+      })
+      .rpc();
+  });
+
+  it("registers the LP mint with its pool reserve", async () => {
+    const config = await program.account.stakingMintConfig.fetch(
+      pda([Buffer.from("staking_mint"), lpMint.toBuffer()])
+    );
+
+    assert.isTrue(config.isLpToken);
+    assert.isTrue(config.lpReserveAccount.equals(poolReserve));
+    assert.equal(config.lpRate.toString(), initialRate.toString());
+  });
+
+  it("values staked LP tokens at their share of the native reserve", async () => {
+    await program.methods
+      .stake(0, new BN(STAKED_LP), 0)
+      .accountsPartial(stakeAccounts())
+      .signers([user])
+      .rpc();
+
+    const position = await program.account.userStake.fetch(userStake());
+    const expectedPower = (STAKED_LP * NATIVE_RESERVE) / LP_SUPPLY;
+
+    assert.equal(position.amount.toNumber(), STAKED_LP);
+    assert.equal(position.powerAmount.toNumber(), expectedPower);
+
+    // Fresh stakes count at the flash-stake discount (50%)
+    const summary = await program.account.userStakeSummary.fetch(
+      stakeAccounts().userStakeSummary
+    );
+    assert.equal(summary.effectiveAmount.toNumber(), expectedPower / 2);
+    assert.equal(summary.tier, 3);
+  });
+
+  it("ignores reserve donations when valuing positions", async () => {
+    // Reserve doubles, e.g. a donation or a flash swap into the pool
+    await mintTo(connection, admin, nativeMint, poolReserve, admin, NATIVE_RESERVE);
+
+    await program.methods
+      .unstake(new BN(STAKED_LP / 2))
+      .accountsPartial(stakeAccounts())
+      .signers([user])
+      .rpc();
+
+    const position = await program.account.userStake.fetch(userStake());
+
+    assert.equal(position.amount.toNumber(), STAKED_LP / 2);
+    assert.equal(
+      position.powerAmount.toNumber(),
+      ((STAKED_LP / 2) * NATIVE_RESERVE) / LP_SUPPLY
+    );
+  });
+
+  it("rejects LP rate updates inside the update interval", async () => {
+    try {
+      await program.methods
+        .updateLpRate()
+        .accountsPartial({
+          stakingMintConfig: pda([Buffer.from("staking_mint"), lpMint.toBuffer()]),
+          stakingTokenMint: lpMint,
+          lpReserveAccount: poolReserve,
+        })
+        .rpc();
+      assert.fail("update should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "LpRateUpdateTooEarly");
+    }
+  });
+
+  it("revalues positions at the stored LP rate on refresh", async () => {
+    const config = pda([Buffer.from("staking_mint"), lpMint.toBuffer()]);
+
+    await program.methods
+      .refreshStakeSummary()
+      .accountsPartial({
+        userStakeSummary: stakeAccounts().userStakeSummary,
+        globalState,
+      })
+      .remainingAccounts([
+        { pubkey: userStake(), isSigner: false, isWritable: true },
+        { pubkey: config, isSigner: false, isWritable: false },
+      ])
+      .rpc();
+
+    const position = await program.account.userStake.fetch(userStake());
+    const expectedPower = ((STAKED_LP / 2) * NATIVE_RESERVE) / LP_SUPPLY;
+
+    assert.equal(position.powerAmount.toNumber(), expectedPower);
+    assert.equal(position.mintWeightBps, 10_000);
+  });
+
+  it("lets a delegate stake for the user but never unstake", async () => {
+    const delegate = Keypair.generate();
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(delegate.publicKey, LAMPORTS_PER_SOL)
+    );
+    const delegateLpAccount = await createAccount(
+      connection,
+      admin,
+      lpMint,
+      delegate.publicKey
+    );
+    await mintTo(connection, admin, lpMint, delegateLpAccount, admin, STAKED_LP);
+
+    await program.methods
+      .setStakeDelegate(delegate.publicKey)
+      .accountsPartial({
+        user: user.publicKey,
+        userStakeSummary: stakeAccounts().userStakeSummary,
+      })
+      .signers([user])
+      .rpc();
+
+    const position = pda([
+      Buffer.from("user_stake"),
+      user.publicKey.toBuffer(),
+      lpMint.toBuffer(),
+      positionIndex(1),
+    ]);
+
+    await program.methods
+      .stakeFor(1, new BN(STAKED_LP), 0)
+      .accountsPartial({
+        payer: delegate.publicKey,
+        authority: delegate.publicKey,
+        beneficiary: user.publicKey,
+        userStake: position,
+        userStakeSummary: stakeAccounts().userStakeSummary,
+        payerTokenAccount: delegateLpAccount,
+        stakeTokenAccount: pda([
+          Buffer.from("user_stake"),
+          user.publicKey.toBuffer(),
+          lpMint.toBuffer(),
+          positionIndex(1),
+          Buffer.from("token_account"),
+        ]),
+        stakingTokenMint: lpMint,
+        stakingMintConfig: pda([Buffer.from("staking_mint"), lpMint.toBuffer()]),
+        stakingPool: pda([Buffer.from("staking_pool"), lpMint.toBuffer()]),
+        globalState,
+      })
+      .signers([delegate])
+      .rpc();
+
+    assert.isTrue(
+      (await program.account.userStake.fetch(position)).user.equals(user.publicKey)
+    );
+
+    // The user's positions are only found under the user's own key
+    try {
+      await program.methods
+        .unstake(new BN(STAKED_LP / 2))
+        .accountsPartial({ ...stakeAccounts(), user: delegate.publicKey })
+        .signers([delegate])
+        .rpc();
+      assert.fail("delegate unstake should have failed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ConstraintSeeds");
+    }
+  });
+});
@@ -0,0 +1,438 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program, BN } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  Signer,
+  Transaction,
+  sendAndConfirmTransaction,
+} from "@solana/web3.js";
+import {
+  createAccount,
+  createMint,
+  getAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import { Protocol } from "../target/types/protocol";
+
+describe("presale applications", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.Protocol as Program<Protocol>;
+  const connection = provider.connection;
+  const admin = (provider.wallet as anchor.Wallet).payer;
+
+  const creator = Keypair.generate();
+
+  const TOKENS_FOR_SALE = 1_000_000;
+  const TOKENS_FOR_LIQUIDITY = 800_000;
+  const TOKEN_PRICE = 1;
+  const LISTING_DEPOSIT = LAMPORTS_PER_SOL / 10;
+
+  const [globalState] = PublicKey.findProgramAddressSync(
+    [Buffer.from("global_state")],
+    program.programId
+  );
+
+  let treasury: PublicKey;
+
+  const pda = (seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+  const presaleAccounts = (mint: PublicKey, presaleId: BN) => {
+    const idSeed = presaleId.toArrayLike(Buffer, "le", 8);
+    const presale = pda([
+      Buffer.from("presale"),
+      mint.toBuffer(),
+      creator.publicKey.toBuffer(),
+      idSeed,
+    ]);
+
+    return {
+      presale,
+      presaleTokenAccount: pda([
+        Buffer.from("presale"),
+        mint.toBuffer(),
+        creator.publicKey.toBuffer(),
+        idSeed,
+        Buffer.from("token_account"),
+      ]),
+      presaleMetadata: pda([
+        Buffer.from("presale_metadata"),
+        presale.toBuffer(),
+      ]),
+      project: pda([
+        Buffer.from("project"),
+        mint.toBuffer(),
+        creator.publicKey.toBuffer(),
+      ]),
+    };
+  };
+
+  // The creator pays the transaction fees so treasury balances only move
+  // by what the program transfers
+  const sendAsCreator = async (tx: Transaction, signers: Signer[] = []) =>
+    sendAndConfirmTransaction(connection, tx, [creator, ...signers]);
+
+  const lamports = (account: PublicKey) => connection.getBalance(account);
+
+  const tokenBalance = async (account: PublicKey) =>
+    Number((await getAccount(connection, account)).amount);
+
+  const setApplicationFees = (
+    fee: number,
+    inStakingToken: boolean,
+    deposit: number
+  ) =>
+    program.methods
+      .setApplicationFees(new BN(fee), inStakingToken, new BN(deposit))
+      .accountsPartial({ admin: admin.publicKey, globalState })
+      .rpc();
+
+  // Creates a pending presale for a fresh mint, so rounds never overlap
+  const createPresale = async (feeAccounts = {}) => {
+    const mint = await createMint(connection, admin, admin.publicKey, null, 6);
+    const creatorTokenAccount = await createAccount(
+      connection,
+      admin,
+      mint,
+      creator.publicKey
+    );
+    await mintTo(
+      connection,
+      admin,
+      mint,
+      creatorTokenAccount,
+      admin,
+      2 * (TOKENS_FOR_SALE + TOKENS_FOR_LIQUIDITY)
+    );
+
+    const { totalPresales } = await program.account.globalState.fetch(
+      globalState
+    );
+    const accounts = presaleAccounts(mint, totalPresales);
+    const now = Math.floor(Date.now() / 1000);
+
+    const tx = await program.methods
+      .createPresale(
+        {
+          tokensForSale: new BN(TOKENS_FOR_SALE),
+          tokensForLiquidity: new BN(TOKENS_FOR_LIQUIDITY),
+          tokenPrice: new BN(TOKEN_PRICE),
+          registrationStartTime: new BN(now + 600),
+          registrationEndTime: new BN(now + 1_200),
+          startTime: new BN(now + 1_800),
+          endTime: new BN(now + 3_600),
+          listingPrice: new BN(TOKEN_PRICE),
+          vestingEnabled: false,
+        },
+        {
+          name: "Test",
+          symbol: "TST",
+          uri: "",
+          contentHash: Array(32).fill(0),
+        }
+      )
+      .accountsPartial({
+        creator: creator.publicKey,
+        globalState,
+        projectVault: null,
+        creatorTokenAccount,
+        mintOfTokenBeingSold: mint,
+        treasuryWallet: null,
+        creatorFeeTokenAccount: null,
+        treasuryFeeTokenAccount: null,
+        ...accounts,
+        ...feeAccounts,
+      })
+      .transaction();
+
+    await sendAsCreator(tx);
+
+    return { mint, creatorTokenAccount, ...accounts };
+  };
+
+  before(async () => {
+    await connection.confirmTransaction(
+      await connection.requestAirdrop(creator.publicKey, 10 * LAMPORTS_PER_SOL)
+    );
+
+    const existing = await program.account.globalState.fetchNullable(
+      globalState
+    );
+
+    if (!existing) {
+      const nativeMint = await createMint(
+        connection,
+        admin,
+        admin.publicKey,
+        null,
+        6
+      );
+      await program.methods
+        .initialize(nativeMint, admin.publicKey)
+        .accountsPartial({ admin: admin.publicKey, globalState })
+        .rpc();
+    }
+
+    treasury = (await program.account.globalState.fetch(globalState))
+      .treasuryWallet;
+  });
+
+  afterEach(async () => {
+    await setApplicationFees(0, false, 0);
+  });
+
+  it("refunds the listing deposit on approval", async () => {
+    await setApplicationFees(0, false, LISTING_DEPOSIT);
+
+    const round = await createPresale();
+    const presale = await program.account.presale.fetch(round.presale);
+    assert.equal(presale.listingDeposit.toNumber(), LISTING_DEPOSIT);
+
+    const creatorBefore = await lamports(creator.publicKey);
+
+    await program.methods
+      .approvePresale()
+      .accountsPartial({
+        admin: admin.publicKey,
+        creator: creator.publicKey,
+        presale: round.presale,
+        globalState,
+      })
+      .rpc();
+
+    assert.equal(
+      (await lamports(creator.publicKey)) - creatorBefore,
+      LISTING_DEPOSIT
+    );
+    assert.equal(
+      (await program.account.presale.fetch(round.presale)).listingDeposit.toNumber(),
+      0
+    );
+  });
+
+  it("charges the application fee in SOL", async () => {
+    const fee = LAMPORTS_PER_SOL / 20;
+    await setApplicationFees(fee, false, 0);
+
+    const treasuryBefore = await lamports(treasury);
+    await createPresale({ treasuryWallet: treasury });
+
+    assert.equal((await lamports(treasury)) - treasuryBefore, fee);
+  });
+
+  it("charges the application fee in the staking token", async () => {
+    const fee = 5_000;
+    await setApplicationFees(fee, true, 0);
+
+    const { stakingTokenMint } = await program.account.globalState.fetch(
+      globalState
+    );
+    const creatorFeeTokenAccount = await createAccount(
+      connection,
+      admin,
+      stakingTokenMint,
+      creator.publicKey
+    );
+    const treasuryFeeTokenAccount = await createAccount(
+      connection,
+      admin,
+      stakingTokenMint,
+      treasury,
+      Keypair.generate()
+    );
+    await mintTo(
+      connection,
+      admin,
+      stakingTokenMint,
+      creatorFeeTokenAccount,
+      admin,
+      fee
+    );
+
+    await createPresale({ creatorFeeTokenAccount, treasuryFeeTokenAccount });
+
+    assert.equal(await tokenBalance(creatorFeeTokenAccount), 0);
+    assert.equal(await tokenBalance(treasuryFeeTokenAccount), fee);
+  });
+
+  it("tops up and withdraws the deposit as the supply is updated", async () => {
+    const round = await createPresale();
+    const deposit = TOKENS_FOR_SALE + TOKENS_FOR_LIQUIDITY;
+    const now = Math.floor(Date.now() / 1000);
+
+    const updatePresale = (tokensForSale: number, tokensForLiquidity: number) =>
+      program.methods
+        .updatePresale({
+          tokensForSale: new BN(tokensForSale),
+          tokensForLiquidity: new BN(tokensForLiquidity),
+          tokenPrice: new BN(TOKEN_PRICE),
+          registrationStartTime: new BN(now + 600),
+          registrationEndTime: new BN(now + 1_200),
+          startTime: new BN(now + 1_800),
+          endTime: new BN(now + 3_600),
+          listingPrice: new BN(TOKEN_PRICE),
+        })
+        .accountsPartial({
+          creator: creator.publicKey,
+          presale: round.presale,
+          project: round.project,
+          presaleTokenAccount: round.presaleTokenAccount,
+          creatorTokenAccount: round.creatorTokenAccount,
+          globalState,
+        })
+        .signers([creator])
+        .rpc();
+
+    // Raising the supply pulls the difference from the creator
+    await updatePresale(TOKENS_FOR_SALE * 1.5, TOKENS_FOR_LIQUIDITY * 1.5);
+
+    assert.equal(await tokenBalance(round.presaleTokenAccount), deposit * 1.5);
+    assert.equal(await tokenBalance(round.creatorTokenAccount), deposit * 0.5);
+
+    let project = await program.account.project.fetch(round.project);
+    assert.equal(project.tokensDeposited.toNumber(), deposit * 1.5);
+    assert.equal(project.tokensAllocated.toNumber(), deposit * 1.5);
+
+    // Lowering it sends the surplus back
+    await updatePresale(TOKENS_FOR_SALE / 2, TOKENS_FOR_LIQUIDITY / 2);
+
+    assert.equal(await tokenBalance(round.presaleTokenAccount), deposit / 2);
+    assert.equal(await tokenBalance(round.creatorTokenAccount), deposit * 1.5);
+
+    project = await program.account.project.fetch(round.project);
+    assert.equal(project.tokensDeposited.toNumber(), deposit / 2);
+    assert.equal(project.tokensAllocated.toNumber(), deposit / 2);
+
+    const presale = await program.account.presale.fetch(round.presale);
+    assert.equal(presale.tokensForSale.toNumber(), TOKENS_FOR_SALE / 2);
+    assert.equal(presale.tokensForLiquidity.toNumber(), TOKENS_FOR_LIQUIDITY / 2);
+  });
+
+  it("returns the tokens and forfeits the deposit on rejection", async () => {
+    await setApplicationFees(0, false, LISTING_DEPOSIT);
+
+    const round = await createPresale();
+    assert.equal(
+      await tokenBalance(round.creatorTokenAccount),
+      TOKENS_FOR_SALE + TOKENS_FOR_LIQUIDITY
+    );
+
+    const treasuryBefore = await lamports(treasury);
+
+    const tx = await program.methods
+      .rejectPresale(1)
+      .accountsPartial({
+        admin: admin.publicKey,
+        creator: creator.publicKey,
+        presale: round.presale,
+        presaleMetadata: round.presaleMetadata,
+        orderBook: null,
+        project: round.project,
+        presaleTokenAccount: round.presaleTokenAccount,
+        creatorTokenAccount: round.creatorTokenAccount,
+        globalState,
+        treasuryWallet: treasury,
+      })
+      .transaction();
+    await sendAsCreator(tx, [admin]);
+
+    assert.equal(
+      await tokenBalance(round.creatorTokenAccount),
+      2 * (TOKENS_FOR_SALE + TOKENS_FOR_LIQUIDITY)
+    );
+    assert.equal((await lamports(treasury)) - treasuryBefore, LISTING_DEPOSIT);
+
+    assert.isNull(await connection.getAccountInfo(round.presale));
+    assert.isNull(await connection.getAccountInfo(round.presaleTokenAccount));
+
+    const project = await program.account.project.fetch(round.project);
+    assert.equal(project.tokensDeposited.toNumber(), 0);
+    assert.equal(project.tokensAllocated.toNumber(), 0);
+  });
+
+  it("rejects allowlist entries until the presale is approved", async () => {
+    const round = await createPresale();
+    const user = Keypair.generate().publicKey;
+
+    try {
+      await program.methods
+        .addToAllowlist(user, new BN(1_000))
+        .accountsPartial({
+          creator: creator.publicKey,
+          presale: round.presale,
+          allowlistEntry: pda([
+            Buffer.from("allowlist"),
+            round.presale.toBuffer(),
+            user.toBuffer(),
+          ]),
+        })
+        .signers([creator])
+        .rpc();
+      assert.fail("allowlist entry should have been rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidPresaleStatus");
+    }
+  });
+
+  it("lets only the creator edit metadata until approval", async () => {
+    const round = await createPresale();
+    const stranger = Keypair.generate();
+
+    const updateMetadata = (signer: Keypair, name: string) =>
+      program.methods
+        .updatePresaleMetadata({
+          name,
+          symbol: "TST",
+          uri: "https://example.com/presale.json",
+          contentHash: Array(32).fill(1),
+        })
+        .accountsPartial({
+          creator: signer.publicKey,
+          presale: round.presale,
+          presaleMetadata: round.presaleMetadata,
+        })
+        .signers([signer])
+        .rpc();
+
+    await updateMetadata(creator, "Renamed");
+    const metadata = await program.account.presaleMetadata.fetch(
+      round.presaleMetadata
+    );
+    assert.equal(metadata.name, "Renamed");
+    assert.equal(metadata.uri, "https://example.com/presale.json");
+
+    try {
+      await updateMetadata(stranger, "Hijacked");
+      assert.fail("stranger update should have been rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+
+    await program.methods
+      .approvePresale()
+      .accountsPartial({
+        admin: admin.publicKey,
+        creator: creator.publicKey,
+        presale: round.presale,
+        globalState,
+      })
+      .rpc();
+
+    try {
+      await updateMetadata(creator, "Too late");
+      assert.fail("update after approval should have been rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidPresaleStatus");
+    }
+
+    assert.equal(
+      (await program.account.presaleMetadata.fetch(round.presaleMetadata)).name,
+      "Renamed"
+    );
+  });
+});